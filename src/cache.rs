@@ -0,0 +1,44 @@
+use anyhow::{Context, Result};
+use std::path::PathBuf;
+
+use crate::config::xdg_dir;
+
+/// Returns the copy of `url` saved by an earlier download, for use with `--offline`
+pub fn load(url: &str) -> Option<String> {
+    std::fs::read_to_string(path_for(url)?).ok()
+}
+
+/// Saves the body downloaded from `url`, replacing any older copy.
+///
+/// Every successful download is stored, and `pycargo prefetch` fills the cache ahead of time.
+pub fn store(url: &str, body: &str) -> Result<PathBuf> {
+    let path = path_for(url).context("Could not determine the cache directory")?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    std::fs::write(&path, body).with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+/// Cache file for a URL, named after the URL so different sources never collide
+fn path_for(url: &str) -> Option<PathBuf> {
+    let name: String = url
+        .trim_start_matches("https://")
+        .trim_start_matches("http://")
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    Some(cache_dir()?.join(name))
+}
+
+/// Location of cached downloads, honouring `XDG_CACHE_HOME`
+fn cache_dir() -> Option<PathBuf> {
+    Some(xdg_dir("XDG_CACHE_HOME", ".cache", "LOCALAPPDATA")?.join("downloads"))
+}
@@ -0,0 +1,265 @@
+use clap::{Args, Parser, Subcommand};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::config::ConfigKey;
+use crate::gitignore::Section;
+use crate::output::OutputFormat;
+use crate::templates::SetupType;
+
+/// PyCargo – Bootstrap a Python Data Science Project
+#[derive(Parser)]
+#[command(author, version, about)]
+pub struct Cli {
+    #[command(flatten)]
+    pub global: GlobalArgs,
+
+    #[command(subcommand)]
+    pub command: Commands,
+}
+
+/// Options accepted by every subcommand
+#[derive(Args, Clone)]
+pub struct GlobalArgs {
+    /// Print every directory, file, command and API call without performing them
+    #[arg(long, global = true, env = "PYCARGO_DRY_RUN")]
+    pub dry_run: bool,
+
+    /// Never prompt; fail with an error when a value is missing instead
+    #[arg(
+        long,
+        visible_alias = "yes",
+        short_alias = 'y',
+        global = true,
+        env = "PYCARGO_NON_INTERACTIVE"
+    )]
+    pub non_interactive: bool,
+
+    /// How to report progress [default: fancy on a terminal, plain otherwise]
+    #[arg(long, value_enum, global = true, env = "PYCARGO_OUTPUT")]
+    pub output: Option<OutputFormat>,
+
+    /// Stream the output of uv, git and pip while they run
+    #[arg(short, long, global = true, env = "PYCARGO_VERBOSE")]
+    pub verbose: bool,
+
+    /// Work without network access: run uv offline, use cached downloads and skip GitHub
+    #[arg(long, global = true, env = "PYCARGO_OFFLINE")]
+    pub offline: bool,
+
+    /// PEM bundle of extra CA certificates to trust, e.g. for a TLS-intercepting proxy
+    #[arg(long, global = true, value_name = "PATH", env = "PYCARGO_CA_BUNDLE")]
+    pub ca_bundle: Option<PathBuf>,
+
+    /// Write the command log here instead of the user log directory
+    #[arg(long, global = true, value_name = "PATH", env = "PYCARGO_LOG_FILE")]
+    pub log_file: Option<PathBuf>,
+}
+
+#[derive(Subcommand)]
+pub enum Commands {
+    /// Create a new project directory and bootstrap it
+    New(NewArgs),
+
+    /// Bootstrap an existing directory, keeping any files already present
+    Init(InitArgs),
+
+    /// Continue a setup that failed part-way, or discard it with --abort
+    Resume(ResumeArgs),
+
+    /// Fill the uv and download caches for a template, so `--offline` setups can use them
+    Prefetch(PrefetchArgs),
+
+    /// Start a shell with the project's virtual environment activated
+    Shell(ShellArgs),
+
+    /// Check the tools, credentials and network access pycargo depends on
+    Doctor,
+
+    /// Manage the built-in requirement templates
+    #[command(subcommand)]
+    Templates(TemplatesCommand),
+
+    /// Read and change defaults in the user config file
+    #[command(subcommand)]
+    Config(ConfigCommand),
+}
+
+#[derive(Args)]
+pub struct NewArgs {
+    /// Name of the project directory
+    #[arg(short, long, env = "PYCARGO_NAME")]
+    pub name: String,
+
+    /// Flag to indicate if a GitHub repo should be created [env: PYCARGO_GITHUB_REPO]
+    #[arg(short = 'g', long)]
+    pub github_repo: bool,
+
+    /// Skip creating a GitHub repo even if the config file enables it
+    #[arg(long, conflicts_with = "github_repo")]
+    pub no_github_repo: bool,
+
+    /// Optional custom name for the GitHub repo
+    #[arg(
+        long,
+        value_name = "GITHUB_REPO_NAME",
+        env = "PYCARGO_GITHUB_REPO_NAME"
+    )]
+    pub github_repo_name: Option<String>,
+
+    /// Specify if the GitHub repository should be private [env: PYCARGO_PRIVATE]
+    #[arg(short = 'p', long)]
+    pub private: bool,
+
+    /// Make the GitHub repository public even if the config file sets it private
+    #[arg(long, conflicts_with = "private")]
+    pub public: bool,
+
+    #[command(flatten)]
+    pub project: ProjectArgs,
+}
+
+impl NewArgs {
+    /// GitHub repository name, defaulting to the project directory name
+    pub fn repo_name(&self) -> String {
+        self.github_repo_name.clone().unwrap_or_else(|| {
+            std::path::Path::new(&self.name)
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_else(|| self.name.clone())
+        })
+    }
+}
+
+#[derive(Args)]
+pub struct InitArgs {
+    /// Directory to bootstrap (defaults to the current directory)
+    #[arg(default_value = ".", env = "PYCARGO_PATH")]
+    pub path: PathBuf,
+
+    #[command(flatten)]
+    pub project: ProjectArgs,
+}
+
+#[derive(Args)]
+pub struct ResumeArgs {
+    /// Project directory passed to `new`, or the directory passed to `init`
+    #[arg(default_value = ".")]
+    pub path: PathBuf,
+
+    /// Undo the unfinished setup instead of continuing it
+    #[arg(long, env = "PYCARGO_ABORT")]
+    pub abort: bool,
+
+    /// Print the virtual environment activation command for every shell, not just the current one
+    #[arg(long, conflicts_with = "abort", env = "PYCARGO_ALL_SHELLS")]
+    pub all_shells: bool,
+}
+
+#[derive(Args)]
+pub struct PrefetchArgs {
+    /// Requirement set to download packages for [default: advanced] [env: PYCARGO_SETUP]
+    #[arg(short = 's', long, value_enum)]
+    pub setup: Option<SetupType>,
+
+    /// Python version to install and resolve packages for [env: PYCARGO_PYTHON]
+    #[arg(long, value_name = "VERSION")]
+    pub python: Option<String>,
+    /// License whose text to download [default: Apache-2.0] [env: PYCARGO_LICENSE]
+    #[arg(long, value_name = "SPDX")]
+    pub license: Option<String>,
+
+    /// Sections of `.gitignore` to download [default: python] [env: PYCARGO_GITIGNORE]
+    #[arg(long, value_enum, value_delimiter = ',', value_name = "SECTIONS")]
+    pub gitignore: Option<Vec<Section>>,
+}
+
+#[derive(Args)]
+pub struct ShellArgs {
+    /// Shell to start instead of the current one, e.g. `fish` or `/usr/bin/zsh`
+    #[arg(value_name = "SHELL", env = "PYCARGO_SHELL")]
+    pub shell: Option<PathBuf>,
+}
+
+/// Options shared by `new` and `init`.
+///
+/// Unset values fall back to `PYCARGO_*` environment variables, then the config file.
+#[derive(Args)]
+pub struct ProjectArgs {
+    /// Requirement set to install [default: advanced] [env: PYCARGO_SETUP]
+    #[arg(short = 's', long, value_enum)]
+    pub setup: Option<SetupType>,
+
+    /// Python version for the environment, e.g. 3.12, installed by uv if missing [env: PYCARGO_PYTHON]
+    #[arg(long, value_name = "VERSION")]
+    pub python: Option<String>,
+
+    /// License of the project: MIT, BSD-3-Clause, Apache-2.0, GPL-3.0-only, GPL-3.0-or-later
+    /// or proprietary [default: Apache-2.0] [env: PYCARGO_LICENSE]
+    #[arg(long, value_name = "SPDX")]
+    pub license: Option<String>,
+
+    /// Comma-separated sections to build `.gitignore` from: python, jupyter, vscode,
+    /// jetbrains, macos, linux, windows [default: python] [env: PYCARGO_GITIGNORE]
+    #[arg(long, value_enum, value_delimiter = ',', value_name = "SECTIONS")]
+    pub gitignore: Option<Vec<Section>>,
+
+    /// Name of the initial git branch [default: main] [env: PYCARGO_BRANCH]
+    #[arg(long, value_name = "BRANCH")]
+    pub branch: Option<String>,
+
+    #[command(flatten)]
+    pub identity: GitIdentityArgs,
+
+    /// Print the virtual environment activation command for every shell, not just the current one
+    #[arg(long, env = "PYCARGO_ALL_SHELLS")]
+    pub all_shells: bool,
+}
+
+/// Values used instead of prompting when the git identity is not configured
+#[derive(Args, Clone, Serialize, Deserialize)]
+pub struct GitIdentityArgs {
+    /// Git user.name to configure if it is not set yet
+    #[arg(long, value_name = "NAME", env = "PYCARGO_GIT_NAME")]
+    pub git_name: Option<String>,
+
+    /// Git user.email to configure if it is not set yet
+    #[arg(long, value_name = "EMAIL", env = "PYCARGO_GIT_EMAIL")]
+    pub git_email: Option<String>,
+}
+
+impl GitIdentityArgs {
+    /// Returns the value supplied for a git config key, if any
+    pub fn value_for(&self, key: &str) -> Option<&str> {
+        match key {
+            "user.name" => self.git_name.as_deref(),
+            "user.email" => self.git_email.as_deref(),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Subcommand)]
+pub enum TemplatesCommand {
+    /// List the available setup types and their packages
+    List,
+}
+
+#[derive(Subcommand)]
+pub enum ConfigCommand {
+    /// Print the value of a setting
+    Get {
+        #[arg(value_enum)]
+        key: ConfigKey,
+    },
+
+    /// Change a setting in the config file
+    Set {
+        #[arg(value_enum)]
+        key: ConfigKey,
+        value: String,
+    },
+
+    /// Print the config file path and every setting
+    List,
+}
@@ -0,0 +1,480 @@
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use colored::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt;
+use std::path::PathBuf;
+
+use crate::cli::{ConfigCommand, InitArgs, NewArgs, PrefetchArgs, ProjectArgs};
+use crate::gitignore::{self, Section};
+use crate::license::{self, License};
+use crate::output::Output;
+use crate::source;
+use crate::templates::SetupType;
+
+pub const DEFAULT_LICENSE: &str = "Apache-2.0";
+const DEFAULT_BRANCH: &str = "main";
+
+/// Defaults read from `config.toml`; every key is optional
+#[derive(Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Config {
+    pub setup: Option<SetupType>,
+    pub github_repo: Option<bool>,
+    pub private: Option<bool>,
+    pub license: Option<String>,
+    pub gitignore: Option<Vec<Section>>,
+    pub python: Option<String>,
+    pub branch: Option<String>,
+    pub template_dirs: Option<Vec<PathBuf>>,
+    pub gitignore_source: Option<String>,
+    pub license_source: Option<String>,
+    pub sha256: Option<BTreeMap<String, String>>,
+}
+
+/// Settings that can be read and changed with `pycargo config`
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ConfigKey {
+    /// Default setup type
+    Setup,
+    /// Create a GitHub repository by default
+    GithubRepo,
+    /// Make created GitHub repositories private by default
+    Private,
+    /// SPDX identifier of the project license
+    License,
+    /// Comma-separated sections `.gitignore` is built from
+    Gitignore,
+    /// Python version requested from uv
+    Python,
+    /// Name of the initial git branch
+    Branch,
+    /// Comma-separated directories searched for `<setup>.txt` requirement overrides
+    TemplateDirs,
+    /// Base URL or directory the `.gitignore` sections are read from
+    GitignoreSource,
+    /// Base URL or directory holding `<SPDX-id>.txt` license texts
+    LicenseSource,
+    /// Comma-separated `name=digest` pins for gitignore sections and licenses
+    Sha256,
+}
+
+impl fmt::Display for ConfigKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = self
+            .to_possible_value()
+            .expect("config keys are never skipped");
+        f.write_str(value.get_name())
+    }
+}
+
+impl ConfigKey {
+    /// Environment variable that overrides this key, e.g. `PYCARGO_GITHUB_REPO`
+    pub fn env_var(self) -> String {
+        format!(
+            "PYCARGO_{}",
+            self.to_string().to_uppercase().replace('-', "_")
+        )
+    }
+}
+
+impl Config {
+    /// Loads the config file with `PYCARGO_*` environment overrides applied on top
+    pub fn load() -> Result<Self> {
+        let mut config = Self::load_file()?;
+        config.apply_env()?;
+        Ok(config)
+    }
+
+    /// Loads the config file alone, returning empty defaults if it does not exist
+    fn load_file() -> Result<Self> {
+        let Some(path) = config_path() else {
+            return Ok(Self::default());
+        };
+        match std::fs::read_to_string(&path) {
+            Ok(text) => toml::from_str(&text)
+                .with_context(|| format!("Invalid config file {}", path.display())),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => {
+                Err(err).with_context(|| format!("Failed to read config file {}", path.display()))
+            }
+        }
+    }
+
+    /// Overrides file values with any `PYCARGO_*` variables that are set
+    fn apply_env(&mut self) -> Result<()> {
+        for key in ConfigKey::value_variants() {
+            if let Some(value) = env_override(*key) {
+                self.set(*key, &value)
+                    .with_context(|| format!("Invalid value in {}", key.env_var()))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn save(&self) -> Result<PathBuf> {
+        let path = config_path().context("Could not determine the config directory")?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        let text = toml::to_string_pretty(self).context("Failed to serialize config")?;
+        std::fs::write(&path, text)
+            .with_context(|| format!("Failed to write config file {}", path.display()))?;
+        Ok(path)
+    }
+
+    fn get(&self, key: ConfigKey) -> Option<String> {
+        match key {
+            ConfigKey::Setup => self.setup.map(|s| s.to_string()),
+            ConfigKey::GithubRepo => self.github_repo.map(|b| b.to_string()),
+            ConfigKey::Private => self.private.map(|b| b.to_string()),
+            ConfigKey::License => self.license.clone(),
+            ConfigKey::Gitignore => self.gitignore.as_ref().map(|sections| {
+                sections
+                    .iter()
+                    .map(Section::to_string)
+                    .collect::<Vec<_>>()
+                    .join(",")
+            }),
+            ConfigKey::Python => self.python.clone(),
+            ConfigKey::Branch => self.branch.clone(),
+            ConfigKey::TemplateDirs => self.template_dirs.as_ref().map(|dirs| {
+                dirs.iter()
+                    .map(|d| d.display().to_string())
+                    .collect::<Vec<_>>()
+                    .join(",")
+            }),
+            ConfigKey::GitignoreSource => self.gitignore_source.clone(),
+            ConfigKey::LicenseSource => self.license_source.clone(),
+            ConfigKey::Sha256 => self.sha256.as_ref().map(|pins| {
+                pins.iter()
+                    .map(|(name, digest)| format!("{}={}", name, digest))
+                    .collect::<Vec<_>>()
+                    .join(",")
+            }),
+        }
+    }
+
+    fn set(&mut self, key: ConfigKey, value: &str) -> Result<()> {
+        match key {
+            ConfigKey::Setup => {
+                self.setup = Some(SetupType::from_str(value, true).map_err(|e| anyhow::anyhow!(e))?)
+            }
+            ConfigKey::GithubRepo => self.github_repo = Some(parse_bool(value)?),
+            ConfigKey::Private => self.private = Some(parse_bool(value)?),
+            ConfigKey::License => self.license = Some(license::lookup(value)?.id.to_string()),
+            ConfigKey::Gitignore => {
+                self.gitignore = Some(
+                    value
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|s| !s.is_empty())
+                        .map(|s| Section::from_str(s, true).map_err(|e| anyhow::anyhow!(e)))
+                        .collect::<Result<_>>()?,
+                )
+            }
+            ConfigKey::Python => self.python = Some(value.to_string()),
+            ConfigKey::Branch => self.branch = Some(value.to_string()),
+            ConfigKey::TemplateDirs => {
+                self.template_dirs = Some(
+                    value
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|d| !d.is_empty())
+                        .map(PathBuf::from)
+                        .collect(),
+                )
+            }
+            ConfigKey::GitignoreSource => {
+                source::validate(value)?;
+                self.gitignore_source = Some(value.to_string())
+            }
+            ConfigKey::LicenseSource => {
+                source::validate(value)?;
+                self.license_source = Some(value.to_string())
+            }
+            ConfigKey::Sha256 => self.sha256 = Some(parse_pins(value)?),
+        }
+        Ok(())
+    }
+}
+
+fn env_override(key: ConfigKey) -> Option<String> {
+    env::var(key.env_var()).ok().filter(|v| !v.is_empty())
+}
+
+/// Parses `python=<digest>,MIT=<digest>` into checksum pins
+fn parse_pins(value: &str) -> Result<BTreeMap<String, String>> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|pin| !pin.is_empty())
+        .map(|pin| {
+            let (name, digest) = pin
+                .split_once('=')
+                .with_context(|| format!("Expected name=digest, got '{}'", pin))?;
+            let digest = digest.trim().to_ascii_lowercase();
+            source::validate_sha256(&digest)?;
+            Ok((pin_name(name.trim()), digest))
+        })
+        .collect()
+}
+
+/// Name a checksum is pinned under: the SPDX id for a license alias, otherwise unchanged
+fn pin_name(name: &str) -> String {
+    license::find(name)
+        .map_or(name, |license| license.id)
+        .to_string()
+}
+
+fn parse_bool(value: &str) -> Result<bool> {
+    match value.to_ascii_lowercase().as_str() {
+        "true" | "yes" | "1" => Ok(true),
+        "false" | "no" | "0" => Ok(false),
+        _ => anyhow::bail!("Expected true or false, got '{}'", value),
+    }
+}
+
+/// Location of `config.toml`, honouring `PYCARGO_CONFIG` and `XDG_CONFIG_HOME`
+pub fn config_path() -> Option<PathBuf> {
+    if let Some(path) = env::var_os("PYCARGO_CONFIG").filter(|v| !v.is_empty()) {
+        return Some(PathBuf::from(path));
+    }
+    Some(xdg_dir("XDG_CONFIG_HOME", ".config", "APPDATA")?.join("config.toml"))
+}
+
+/// pycargo's directory below an XDG base directory such as `XDG_CACHE_HOME`.
+///
+/// Without the variable, Windows uses `windows_var` and other platforms
+/// `unix_fallback` below the home directory.
+pub fn xdg_dir(var: &str, unix_fallback: &str, windows_var: &str) -> Option<PathBuf> {
+    let base = match env::var_os(var).filter(|v| !v.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None if cfg!(windows) => PathBuf::from(env::var_os(windows_var)?),
+        None => PathBuf::from(env::var_os("HOME")?).join(unix_fallback),
+    };
+    Some(base.join("pycargo"))
+}
+
+/// A GitHub repository to create for a new project
+#[derive(Clone, Serialize, Deserialize)]
+pub struct GithubRepo {
+    pub name: String,
+    pub private: bool,
+}
+
+/// Where downloaded files come from, and the checksums they must match
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Sources {
+    pub gitignore: Option<String>,
+    pub license: Option<String>,
+    pub sha256: BTreeMap<String, String>,
+}
+
+impl Sources {
+    fn from_config(config: &Config) -> Self {
+        Self {
+            gitignore: config.gitignore_source.clone(),
+            license: config.license_source.clone(),
+            sha256: config
+                .sha256
+                .iter()
+                .flatten()
+                .map(|(name, digest)| (pin_name(name), digest.clone()))
+                .collect(),
+        }
+    }
+}
+
+/// Project options after merging command-line flags with the config file
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ProjectSettings {
+    pub setup: SetupType,
+    pub license: String,
+    #[serde(default = "default_gitignore")]
+    pub gitignore: Vec<Section>,
+    pub python: Option<String>,
+    pub branch: String,
+    pub template_dirs: Vec<PathBuf>,
+    pub github: Option<GithubRepo>,
+    #[serde(default)]
+    pub sources: Sources,
+}
+
+impl ProjectSettings {
+    pub fn for_new(args: &NewArgs, config: &Config) -> Self {
+        let github_repo = resolve_flag(args.github_repo, args.no_github_repo, config.github_repo);
+        let github = github_repo.then(|| GithubRepo {
+            name: args.repo_name(),
+            private: resolve_flag(args.private, args.public, config.private),
+        });
+        Self::resolve(&args.project, config, github)
+    }
+
+    pub fn for_init(args: &InitArgs, config: &Config) -> Self {
+        Self::resolve(&args.project, config, None)
+    }
+
+    /// Settings for `pycargo prefetch`, which only uses the template, Python and license
+    pub fn for_prefetch(args: &PrefetchArgs, config: &Config) -> Self {
+        Self {
+            setup: args.setup.or(config.setup).unwrap_or(SetupType::Advanced),
+            license: args
+                .license
+                .clone()
+                .or_else(|| config.license.clone())
+                .unwrap_or_else(|| DEFAULT_LICENSE.to_string()),
+            gitignore: args
+                .gitignore
+                .clone()
+                .or_else(|| config.gitignore.clone())
+                .unwrap_or_else(default_gitignore),
+            python: args.python.clone().or_else(|| config.python.clone()),
+            branch: DEFAULT_BRANCH.to_string(),
+            template_dirs: config.template_dirs.clone().unwrap_or_default(),
+            github: None,
+            sources: Sources::from_config(config),
+        }
+    }
+
+    /// Catalog entry of the selected license
+    pub fn license(&self) -> Result<&'static License> {
+        license::lookup(&self.license)
+    }
+
+    /// Where a `.gitignore` section is read from
+    pub fn gitignore_location(&self, section: Section) -> String {
+        let base = self
+            .sources
+            .gitignore
+            .as_deref()
+            .unwrap_or(gitignore::DEFAULT_SOURCE);
+        source::join(base, section.path())
+    }
+
+    /// Where the license text is read from, if it is not only built in
+    pub fn license_location(&self) -> Result<Option<String>> {
+        let license = self.license()?;
+        Ok(match &self.sources.license {
+            Some(base) => Some(source::join(base, &format!("{}.txt", license.id))),
+            None => license.url.map(str::to_string),
+        })
+    }
+
+    /// Checksum pinned for a gitignore section or license, by section name or SPDX id
+    pub fn sha256(&self, name: &str) -> Option<&str> {
+        self.sources
+            .sha256
+            .iter()
+            .find(|(pinned, _)| pinned.eq_ignore_ascii_case(name))
+            .map(|(_, digest)| digest.as_str())
+    }
+
+    fn resolve(args: &ProjectArgs, config: &Config, github: Option<GithubRepo>) -> Self {
+        Self {
+            setup: args.setup.or(config.setup).unwrap_or(SetupType::Advanced),
+            license: args
+                .license
+                .clone()
+                .or_else(|| config.license.clone())
+                .unwrap_or_else(|| DEFAULT_LICENSE.to_string()),
+            gitignore: args
+                .gitignore
+                .clone()
+                .or_else(|| config.gitignore.clone())
+                .unwrap_or_else(default_gitignore),
+            python: args.python.clone().or_else(|| config.python.clone()),
+            branch: args
+                .branch
+                .clone()
+                .or_else(|| config.branch.clone())
+                .unwrap_or_else(|| DEFAULT_BRANCH.to_string()),
+            template_dirs: config.template_dirs.clone().unwrap_or_default(),
+            github,
+            sources: Sources::from_config(config),
+        }
+    }
+}
+
+/// Sections used when `--gitignore` is not given, matching GitHub's Python template alone
+fn default_gitignore() -> Vec<Section> {
+    vec![Section::Python]
+}
+
+/// Resolves a boolean that has both a positive and a negative flag
+fn resolve_flag(on: bool, off: bool, configured: Option<bool>) -> bool {
+    if on {
+        true
+    } else if off {
+        false
+    } else {
+        configured.unwrap_or(false)
+    }
+}
+
+/// Runs a `pycargo config` subcommand
+pub fn run(command: ConfigCommand, out: &Output) -> Result<()> {
+    match command {
+        ConfigCommand::Get { key } => match Config::load()?.get(key) {
+            Some(value) => out.data(value),
+            None => anyhow::bail!("{} is not set", key),
+        },
+        ConfigCommand::Set { key, value } => {
+            // Only the file is rewritten, environment overrides are never persisted
+            let mut config = Config::load_file()?;
+            config.set(key, &value)?;
+            let path = config.save()?;
+            // Show the stored form, e.g. the canonical SPDX id of a license
+            let value = config.get(key).unwrap_or(value);
+            out.success(format!("Set {} = {} in {}", key, value, path.display()));
+            if env_override(key).is_some() {
+                out.warn(format!("{} is set and takes precedence", key.env_var()));
+            }
+        }
+        ConfigCommand::List => {
+            match config_path() {
+                Some(path) => out.data(format!("# {}", path.display()).blue()),
+                None => out.data("# config directory unknown".blue()),
+            }
+            let config = Config::load()?;
+            for key in ConfigKey::value_variants() {
+                let value = config.get(*key).unwrap_or_else(|| "(unset)".to_string());
+                match env_override(*key) {
+                    Some(_) => out.data(format!(
+                        "{} = {} {}",
+                        key.to_string().bold(),
+                        value,
+                        format!("(from {})", key.env_var()).blue()
+                    )),
+                    None => out.data(format!("{} = {}", key.to_string().bold(), value)),
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_pins_normalises_names_and_digests() {
+        let digest = "AB".repeat(32);
+        let pins = parse_pins(&format!(" python={}, GPL-3.0 = {} ,", digest, digest)).unwrap();
+        let lower = "ab".repeat(32);
+        assert_eq!(pins.get("python"), Some(&lower));
+        assert_eq!(pins.get("GPL-3.0-only"), Some(&lower));
+        assert_eq!(pins.len(), 2);
+    }
+
+    #[test]
+    fn parse_pins_rejects_malformed_pins() {
+        assert!(parse_pins("python").is_err());
+        assert!(parse_pins("python=abc").is_err());
+        assert!(parse_pins("").unwrap().is_empty());
+    }
+}
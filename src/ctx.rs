@@ -0,0 +1,399 @@
+use anyhow::{Context, Result};
+use indicatif::ProgressBar;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Mutex;
+use std::time::Instant;
+use tokio::fs;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::Command;
+
+use crate::cli::GlobalArgs;
+use crate::http;
+use crate::log::RunLog;
+use crate::output::{LiveOutput, Output};
+
+/// Shared state for a single pycargo invocation.
+///
+/// Every side effect (directories, files, external commands) goes through
+/// this type so that `--dry-run` can report it instead of performing it.
+pub struct Ctx {
+    /// Directory the project is being bootstrapped in
+    pub root: PathBuf,
+    /// When set, side effects are printed instead of performed
+    pub dry_run: bool,
+    /// When set, missing values are errors instead of prompts
+    pub non_interactive: bool,
+    /// When set, nothing is fetched from the network
+    pub offline: bool,
+    /// Where messages and events are reported
+    pub out: Output,
+    /// Where every command and HTTP request is recorded
+    pub log: RunLog,
+    /// `owner/name` of a GitHub repository created during this run
+    github_repo: Mutex<Option<String>>,
+    /// Held while a step prompts, as steps run concurrently
+    prompt: tokio::sync::Mutex<()>,
+    /// Ids of steps that found nothing to do
+    skipped: Mutex<Vec<String>>,
+}
+
+impl Ctx {
+    pub fn new(root: impl Into<PathBuf>, global: &GlobalArgs, out: &Output, log: &RunLog) -> Self {
+        Self {
+            root: root.into(),
+            dry_run: global.dry_run,
+            non_interactive: global.non_interactive,
+            offline: global.offline,
+            out: out.clone(),
+            log: log.clone(),
+            github_repo: Mutex::new(None),
+            prompt: tokio::sync::Mutex::new(()),
+            skipped: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Records a GitHub repository created by this run so it can be rolled back
+    pub fn record_github_repo(&self, full_name: impl Into<String>) {
+        *self.github_repo.lock().expect("github_repo lock poisoned") = Some(full_name.into());
+    }
+
+    /// Takes the GitHub repository recorded by this run, if any
+    pub fn take_github_repo(&self) -> Option<String> {
+        self.github_repo
+            .lock()
+            .expect("github_repo lock poisoned")
+            .take()
+    }
+
+    /// Asks the user for a line of input with the spinners of running steps hidden.
+    ///
+    /// Prompts from concurrent steps wait for each other instead of overlapping.
+    pub async fn ask(&self, question: impl Display) -> Result<String> {
+        let _guard = self.prompt.lock().await;
+        self.out.hide_progress();
+        self.out.prompt(question);
+        let input = tokio::task::spawn_blocking(read_line).await;
+        self.out.show_progress();
+        input.context("Failed to read input")?
+    }
+
+    /// Returns whether the step with this id finished without doing any work
+    pub fn was_skipped(&self, id: &str) -> bool {
+        self.skipped
+            .lock()
+            .expect("skipped lock poisoned")
+            .iter()
+            .any(|skipped| skipped == id)
+    }
+
+    /// Resolves a path relative to the project root
+    pub fn path(&self, relative: impl AsRef<Path>) -> PathBuf {
+        self.root.join(relative)
+    }
+
+    /// Returns whether a path relative to the project root exists
+    pub async fn exists(&self, relative: impl AsRef<Path>) -> bool {
+        fs::metadata(self.path(relative)).await.is_ok()
+    }
+
+    /// Prints an action that would be performed in dry-run mode
+    pub fn plan(&self, action: impl Display) {
+        self.out.plan(action);
+    }
+
+    /// Prints a success message, unless nothing was actually done
+    pub fn success(&self, message: impl Display) {
+        if !self.dry_run {
+            self.out.success(message);
+        }
+    }
+
+    /// Starts a named step, reporting it as started now and failed if dropped unfinished
+    pub fn step(&self, id: impl Into<String>, message: impl Into<String>) -> Progress<'_> {
+        let (id, message) = (id.into(), message.into());
+        self.out.event(
+            "step_started",
+            serde_json::json!({ "step": id, "message": message }),
+        );
+        let spinner = if self.dry_run {
+            ProgressBar::hidden()
+        } else {
+            self.out.spinner(message)
+        };
+        Progress {
+            ctx: self,
+            id,
+            started: Instant::now(),
+            spinner,
+            done: false,
+        }
+    }
+
+    pub async fn create_dir(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        if self.dry_run {
+            self.plan(format!("create directory {}", path.display()));
+            return Ok(());
+        }
+        fs::create_dir(path)
+            .await
+            .with_context(|| format!("Failed to create directory {}", path.display()))
+    }
+
+    pub async fn rename(&self, from: impl AsRef<Path>, to: impl AsRef<Path>) -> Result<()> {
+        let (from, to) = (from.as_ref(), to.as_ref());
+        if self.dry_run {
+            self.plan(format!("rename {} to {}", from.display(), to.display()));
+            return Ok(());
+        }
+        fs::rename(from, to)
+            .await
+            .with_context(|| format!("Failed to move {} to {}", from.display(), to.display()))
+    }
+
+    /// Writes a file relative to the project root
+    pub async fn write(
+        &self,
+        relative: impl AsRef<Path>,
+        contents: impl AsRef<[u8]>,
+    ) -> Result<()> {
+        let path = self.path(relative);
+        if self.dry_run {
+            self.plan(format!("write file {}", path.display()));
+            return Ok(());
+        }
+        fs::write(&path, contents)
+            .await
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Runs an external command inside the project root
+    pub async fn run(&self, cmd: &str, args: &[&str]) -> Result<()> {
+        self.run_in(&self.root, cmd, args).await
+    }
+
+    /// Runs an external command that does not touch the project, such as
+    /// `git config --global`, in pycargo's own working directory. Steps using it
+    /// can start before the project directory exists.
+    pub async fn run_global(&self, cmd: &str, args: &[&str]) -> Result<()> {
+        self.run_in(Path::new("."), cmd, args).await
+    }
+
+    async fn run_in(&self, cwd: &Path, cmd: &str, args: &[&str]) -> Result<()> {
+        if self.dry_run {
+            self.plan(format!("run `{} {}`", cmd, args.join(" ")));
+            return Ok(());
+        }
+
+        let command_line = format!("{} {}", cmd, args.join(" "));
+        let started = Instant::now();
+        let mut child = Command::new(cmd)
+            .args(args)
+            .current_dir(cwd)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .inspect_err(|err| self.log.spawn_error(&command_line, cwd, err))
+            .with_context(|| format!("Failed to execute: {}", command_line))?;
+
+        // Show output while it arrives, but keep all of it for the log and error messages
+        let live = self.out.live();
+        let stdout = child.stdout.take().expect("stdout is piped");
+        let stderr = child.stderr.take().expect("stderr is piped");
+        let (stdout, stderr, status) =
+            tokio::join!(capture(stdout, &live), capture(stderr, &live), child.wait());
+        drop(live);
+        let status = status.with_context(|| format!("Failed to wait for: {}", command_line))?;
+        let stdout =
+            stdout.with_context(|| format!("Failed to read output of: {}", command_line))?;
+        let stderr =
+            stderr.with_context(|| format!("Failed to read output of: {}", command_line))?;
+
+        let duration = started.elapsed();
+        self.log.command(
+            &command_line,
+            cwd,
+            status.code(),
+            duration,
+            &stdout,
+            &stderr,
+        );
+        self.out.event(
+            "command",
+            serde_json::json!({
+                "command": command_line,
+                "cwd": cwd.display().to_string(),
+                "exit_code": status.code(),
+                "duration_ms": duration.as_millis() as u64,
+                "stdout": stdout,
+                "stderr": stderr,
+            }),
+        );
+
+        if !status.success() {
+            anyhow::bail!(
+                "{}",
+                self.out.failure(format!(
+                    "Command failed: {} {}\nError Output: {}",
+                    cmd,
+                    args.join(" "),
+                    stderr
+                ))
+            );
+        }
+
+        Ok(())
+    }
+
+    pub async fn git_command(&self, args: &[&str]) -> Result<()> {
+        self.run("git", args).await
+    }
+
+    /// Runs uv, keeping it away from the network in offline mode
+    pub async fn uv_command(&self, args: &[&str]) -> Result<()> {
+        self.run("uv", &self.uv_args(args)).await
+    }
+
+    /// Like [`Ctx::uv_command`], for uv commands that do not touch the project
+    pub async fn uv_global_command(&self, args: &[&str]) -> Result<()> {
+        self.run_global("uv", &self.uv_args(args)).await
+    }
+
+    fn uv_args<'a>(&self, args: &[&'a str]) -> Vec<&'a str> {
+        let mut args = args.to_vec();
+        if self.offline {
+            args.push("--offline");
+        }
+        args
+    }
+
+    /// Runs a read-only command inside the project root, see [`query`]
+    pub async fn query(&self, cmd: &str, args: &[&str]) -> Result<std::process::Output> {
+        query(&self.log, &self.root, cmd, args).await
+    }
+
+    /// Sends an HTTP request through the shared client, recording it in the run log
+    pub async fn send(&self, request: reqwest::RequestBuilder) -> Result<reqwest::Response> {
+        http::send(request, &self.log).await
+    }
+}
+
+/// Runs a command that only reads state, such as `git config --get`, and returns its output.
+///
+/// Unlike [`Ctx::run`] it shows nothing, also runs in dry runs and leaves the
+/// exit status to the caller, but it is recorded in `log` like every command.
+pub async fn query(
+    log: &RunLog,
+    cwd: &Path,
+    cmd: &str,
+    args: &[&str],
+) -> Result<std::process::Output> {
+    let command_line = format!("{} {}", cmd, args.join(" "));
+    let started = Instant::now();
+    let output = Command::new(cmd)
+        .args(args)
+        .current_dir(cwd)
+        .stdin(Stdio::null())
+        .output()
+        .await
+        .inspect_err(|err| log.spawn_error(&command_line, cwd, err))
+        .with_context(|| format!("Failed to execute: {}", command_line))?;
+    log.command(
+        &command_line,
+        cwd,
+        output.status.code(),
+        started.elapsed(),
+        &String::from_utf8_lossy(&output.stdout),
+        &String::from_utf8_lossy(&output.stderr),
+    );
+    Ok(output)
+}
+
+fn read_line() -> Result<String> {
+    let mut input = String::new();
+    let read = std::io::stdin()
+        .read_line(&mut input)
+        .context("Failed to read input")?;
+    if read == 0 {
+        anyhow::bail!("No input available on stdin");
+    }
+    Ok(input.trim().to_string())
+}
+
+/// Reads a child's output stream to the end, passing each line to the live view
+async fn capture(stream: impl AsyncRead + Unpin, live: &LiveOutput) -> std::io::Result<String> {
+    let mut reader = BufReader::new(stream);
+    let mut captured = Vec::new();
+    let mut line = Vec::new();
+    while reader.read_until(b'\n', &mut line).await? > 0 {
+        live.line(&String::from_utf8_lossy(&line));
+        captured.append(&mut line);
+    }
+    Ok(String::from_utf8_lossy(&captured).into_owned())
+}
+
+/// Progress of a step, reported as started, finished or failed, with a spinner while it runs
+pub struct Progress<'a> {
+    ctx: &'a Ctx,
+    id: String,
+    started: Instant,
+    spinner: ProgressBar,
+    done: bool,
+}
+
+impl Progress<'_> {
+    /// Hides the spinner while waiting for user input
+    pub fn pause(&self) {
+        self.spinner.finish_and_clear();
+    }
+
+    /// Marks the step as finished and prints its success message
+    pub fn finish(mut self, message: impl Display) {
+        self.complete(false);
+        self.ctx.success(message);
+    }
+
+    /// Marks the step as finished without doing any work
+    pub fn skip(mut self, message: impl Display) {
+        self.complete(true);
+        self.ctx
+            .skipped
+            .lock()
+            .expect("skipped lock poisoned")
+            .push(self.id.clone());
+        self.ctx.out.success(message);
+    }
+
+    fn complete(&mut self, skipped: bool) {
+        self.done = true;
+        self.spinner.finish_and_clear();
+        self.ctx.out.event(
+            "step_finished",
+            serde_json::json!({
+                "step": self.id,
+                "duration_ms": self.started.elapsed().as_millis() as u64,
+                "skipped": skipped,
+            }),
+        );
+    }
+}
+
+impl Drop for Progress<'_> {
+    fn drop(&mut self) {
+        if self.done {
+            return;
+        }
+        self.spinner.finish_and_clear();
+        self.ctx.out.event(
+            "step_failed",
+            serde_json::json!({
+                "step": self.id,
+                "duration_ms": self.started.elapsed().as_millis() as u64,
+            }),
+        );
+    }
+}
@@ -0,0 +1,163 @@
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+
+/// Where GitHub's gitignore templates are downloaded from unless `gitignore-source` is set
+pub const DEFAULT_SOURCE: &str = "https://raw.githubusercontent.com/github/gitignore/main";
+
+/// A part of `.gitignore` that can be selected with `--gitignore`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Section {
+    Python,
+    Jupyter,
+    Vscode,
+    Jetbrains,
+    Macos,
+    Linux,
+    Windows,
+}
+
+impl Section {
+    /// Path of the template in GitHub's gitignore repository, and in mirrors of it
+    pub fn path(self) -> &'static str {
+        match self {
+            Section::Python => "Python.gitignore",
+            Section::Jupyter => "community/Python/JupyterNotebooks.gitignore",
+            Section::Vscode => "Global/VisualStudioCode.gitignore",
+            Section::Jetbrains => "Global/JetBrains.gitignore",
+            Section::Macos => "Global/macOS.gitignore",
+            Section::Linux => "Global/Linux.gitignore",
+            Section::Windows => "Global/Windows.gitignore",
+        }
+    }
+
+    /// Copy of the template built into pycargo, used when it cannot be downloaded
+    pub fn text(self) -> &'static str {
+        match self {
+            Section::Python => include_str!("../templates/gitignore/Python.gitignore"),
+            Section::Jupyter => include_str!("../templates/gitignore/JupyterNotebooks.gitignore"),
+            Section::Vscode => include_str!("../templates/gitignore/VisualStudioCode.gitignore"),
+            Section::Jetbrains => include_str!("../templates/gitignore/JetBrains.gitignore"),
+            Section::Macos => include_str!("../templates/gitignore/macOS.gitignore"),
+            Section::Linux => include_str!("../templates/gitignore/Linux.gitignore"),
+            Section::Windows => include_str!("../templates/gitignore/Windows.gitignore"),
+        }
+    }
+}
+
+impl fmt::Display for Section {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = self
+            .to_possible_value()
+            .expect("gitignore sections are never skipped");
+        f.write_str(value.get_name())
+    }
+}
+
+/// Adds the sections to an existing `.gitignore` (empty if there is none).
+///
+/// Each section gets a `### name ###` heading and keeps its comments, but
+/// patterns already in the file or in an earlier section are dropped, and a
+/// section with no new patterns is left out, so running this again changes
+/// nothing. Returns the updated file and the names of the sections added.
+pub fn compose(existing: &str, sections: &[(String, String)]) -> (String, Vec<String>) {
+    let mut seen: HashSet<String> = existing
+        .split('\n')
+        .filter(|line| is_pattern(line))
+        .map(|line| line.trim().to_string())
+        .collect();
+    let mut composed = existing.to_string();
+    let mut added = Vec::new();
+
+    for (name, text) in sections {
+        let mut lines = Vec::new();
+        let mut new_patterns = false;
+        for line in text.split('\n') {
+            if is_pattern(line) {
+                if !seen.insert(line.trim().to_string()) {
+                    continue;
+                }
+                new_patterns = true;
+            } else if line.trim().is_empty()
+                && lines.last().is_none_or(|l: &&str| l.trim().is_empty())
+            {
+                // Dropped duplicates can leave runs of blank lines behind
+                continue;
+            }
+            lines.push(line);
+        }
+        if !new_patterns {
+            continue;
+        }
+        while lines.last().is_some_and(|l| l.trim().is_empty()) {
+            lines.pop();
+        }
+
+        if !composed.is_empty() {
+            if !composed.ends_with('\n') {
+                composed.push('\n');
+            }
+            composed.push('\n');
+        }
+        composed.push_str(&format!("### {} ###\n", name));
+        for line in lines {
+            composed.push_str(line);
+            composed.push('\n');
+        }
+        added.push(name.clone());
+    }
+    (composed, added)
+}
+
+/// Whether a line is an ignore pattern rather than a comment or blank line
+fn is_pattern(line: &str) -> bool {
+    let line = line.trim();
+    !line.is_empty() && !line.starts_with('#')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sections() -> Vec<(String, String)> {
+        vec![
+            (
+                "python".to_string(),
+                "# Byte-compiled\n__pycache__/\n*.py[cod]\n\n.env\n".to_string(),
+            ),
+            (
+                "macos".to_string(),
+                "# General\n.DS_Store\n\n# Env\n.env\n".to_string(),
+            ),
+        ]
+    }
+
+    #[test]
+    fn compose_adds_headings_and_drops_duplicates() {
+        let (composed, added) = compose("", &sections());
+        assert_eq!(added, ["python", "macos"]);
+        assert_eq!(
+            composed,
+            "### python ###\n# Byte-compiled\n__pycache__/\n*.py[cod]\n\n.env\n\n\
+             ### macos ###\n# General\n.DS_Store\n\n# Env\n"
+        );
+    }
+
+    #[test]
+    fn compose_again_changes_nothing() {
+        let (first, _) = compose("", &sections());
+        let (second, added) = compose(&first, &sections());
+        assert_eq!(second, first);
+        assert!(added.is_empty());
+    }
+
+    #[test]
+    fn compose_keeps_existing_patterns() {
+        let (composed, added) = compose("*.log\n.DS_Store", &sections());
+        assert!(composed.starts_with("*.log\n.DS_Store\n\n### python ###\n"));
+        assert_eq!(composed.matches(".DS_Store").count(), 1);
+        assert_eq!(added, ["python"]);
+    }
+}
@@ -0,0 +1,125 @@
+use anyhow::{Context, Result};
+use reqwest::{Certificate, Client, Method, RequestBuilder, Response};
+use std::path::Path;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use crate::log::RunLog;
+
+/// How long to wait for the TCP and TLS connection
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long to wait for the response, and for each part of its body
+const READ_TIMEOUT: Duration = Duration::from_secs(20);
+/// Attempts per request, including the first one
+const ATTEMPTS: u32 = 3;
+/// Wait before the first retry, doubled for every retry after it
+const BACKOFF: Duration = Duration::from_millis(500);
+
+static CLIENT: OnceLock<Client> = OnceLock::new();
+
+/// Builds the client every request goes through; called once at startup.
+///
+/// Proxies come from `HTTPS_PROXY`, `HTTP_PROXY`, `ALL_PROXY` and `NO_PROXY`
+/// (or their lowercase forms), which reqwest reads by itself. Certificates in
+/// `ca_bundle` are trusted in addition to the system ones, for proxies that
+/// intercept TLS.
+pub fn init(ca_bundle: Option<&Path>) -> Result<()> {
+    let mut builder = Client::builder()
+        .connect_timeout(CONNECT_TIMEOUT)
+        .user_agent("pycargo");
+    if let Some(path) = ca_bundle {
+        for cert in load_ca_bundle(path)? {
+            builder = builder.add_root_certificate(cert);
+        }
+    }
+    let client = builder.build().context("Failed to create HTTP client")?;
+    CLIENT
+        .set(client)
+        .map_err(|_| anyhow::anyhow!("HTTP client initialized twice"))
+}
+
+/// The shared client, for building requests to pass to [`send`]
+pub fn client() -> &'static Client {
+    CLIENT.get().expect("http::init runs at startup")
+}
+
+/// Reads the PEM certificates of a CA bundle
+pub fn load_ca_bundle(path: &Path) -> Result<Vec<Certificate>> {
+    let pem = std::fs::read(path)
+        .with_context(|| format!("Failed to read CA bundle {}", path.display()))?;
+    let certs = Certificate::from_pem_bundle(&pem)
+        .with_context(|| format!("{} is not a PEM certificate bundle", path.display()))?;
+    if certs.is_empty() {
+        anyhow::bail!("CA bundle {} contains no certificates", path.display());
+    }
+    Ok(certs)
+}
+
+/// Sends a request, recording every attempt in `log`.
+///
+/// Connection errors, timeouts and 5xx responses are retried with exponential
+/// backoff. A POST is only retried if it never reached the server, so a
+/// GitHub repository is not created twice.
+pub async fn send(request: RequestBuilder, log: &RunLog) -> Result<Response> {
+    let request = request.build().context("Failed to build HTTP request")?;
+    let repeatable = request.method() != Method::POST;
+    let (method, url) = (request.method().to_string(), request.url().to_string());
+    let mut delay = BACKOFF;
+
+    for attempt in 1..=ATTEMPTS {
+        let this = request
+            .try_clone()
+            .context("HTTP request body cannot be resent")?;
+        let started = Instant::now();
+        let result = match tokio::time::timeout(READ_TIMEOUT, client().execute(this)).await {
+            Ok(result) => result.map_err(anyhow::Error::from),
+            Err(_) => Err(anyhow::anyhow!(
+                "no response within {} seconds",
+                READ_TIMEOUT.as_secs()
+            )),
+        };
+        log.http(
+            &method,
+            &url,
+            result.as_ref().map(|r| r.status().as_u16()),
+            started.elapsed(),
+        );
+
+        let retry = match &result {
+            Ok(response) => repeatable && response.status().is_server_error(),
+            Err(err) => match err.downcast_ref::<reqwest::Error>() {
+                Some(err) if err.is_connect() => true,
+                Some(err) => repeatable && (err.is_timeout() || err.is_request()),
+                // Our own read timeout
+                None => repeatable,
+            },
+        };
+        if !retry || attempt == ATTEMPTS {
+            return result;
+        }
+        tokio::time::sleep(delay).await;
+        delay *= 2;
+    }
+    unreachable!("the last attempt always returns")
+}
+
+/// Reads a response body as text, failing if the server stops sending for too long
+pub async fn text(mut response: Response) -> Result<String> {
+    let mut body = Vec::new();
+    loop {
+        let chunk = tokio::time::timeout(READ_TIMEOUT, response.chunk())
+            .await
+            .map_err(|_| {
+                anyhow::anyhow!(
+                    "the server sent nothing for {} seconds",
+                    READ_TIMEOUT.as_secs()
+                )
+            })?
+            .context("Failed to read response body")?;
+        match chunk {
+            Some(chunk) => body.extend_from_slice(&chunk),
+            None => break,
+        }
+    }
+    String::from_utf8(body).context("Response body is not valid UTF-8")
+}
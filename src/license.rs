@@ -0,0 +1,129 @@
+/// A license pycargo can write into a project
+pub struct License {
+    /// SPDX identifier accepted by `--license`
+    pub id: &'static str,
+    /// SPDX expression written to the `license` field of pyproject.toml
+    pub expression: &'static str,
+    /// Official source of the text, if it has one
+    pub url: Option<&'static str>,
+    /// Built-in text, used when there is no source or it cannot be downloaded
+    pub text: &'static str,
+    /// Other names accepted for this license
+    aliases: &'static [&'static str],
+}
+
+const GPL_3_URL: &str = "https://www.gnu.org/licenses/gpl-3.0.txt";
+const GPL_3_TEXT: &str = include_str!("../templates/licenses/GPL-3.0.txt");
+
+/// Every license that `--license` accepts
+pub const CATALOG: &[License] = &[
+    License {
+        id: "MIT",
+        expression: "MIT",
+        url: None,
+        text: include_str!("../templates/licenses/MIT.txt"),
+        aliases: &[],
+    },
+    License {
+        id: "BSD-3-Clause",
+        expression: "BSD-3-Clause",
+        url: None,
+        text: include_str!("../templates/licenses/BSD-3-Clause.txt"),
+        aliases: &["BSD-3"],
+    },
+    License {
+        id: "Apache-2.0",
+        expression: "Apache-2.0",
+        url: Some("https://www.apache.org/licenses/LICENSE-2.0.txt"),
+        text: include_str!("../templates/licenses/Apache-2.0.txt"),
+        aliases: &["Apache"],
+    },
+    License {
+        id: "GPL-3.0-only",
+        expression: "GPL-3.0-only",
+        url: Some(GPL_3_URL),
+        text: GPL_3_TEXT,
+        // The deprecated SPDX id means "only"
+        aliases: &["GPL-3.0", "GPL-3"],
+    },
+    License {
+        id: "GPL-3.0-or-later",
+        expression: "GPL-3.0-or-later",
+        url: Some(GPL_3_URL),
+        text: GPL_3_TEXT,
+        aliases: &["GPL-3.0+"],
+    },
+    License {
+        id: "proprietary",
+        expression: "LicenseRef-Proprietary",
+        url: None,
+        text: include_str!("../templates/licenses/proprietary.txt"),
+        aliases: &["LicenseRef-Proprietary"],
+    },
+];
+
+/// Looks up a license by SPDX identifier or alias, ignoring case
+pub fn find(id: &str) -> Option<&'static License> {
+    CATALOG.iter().find(|license| {
+        license.id.eq_ignore_ascii_case(id)
+            || license
+                .aliases
+                .iter()
+                .any(|alias| alias.eq_ignore_ascii_case(id))
+    })
+}
+
+/// Like [`find`], with an error listing the supported licenses
+pub fn lookup(id: &str) -> anyhow::Result<&'static License> {
+    find(id).ok_or_else(|| {
+        let ids: Vec<&str> = CATALOG.iter().map(|license| license.id).collect();
+        anyhow::anyhow!(
+            "Unsupported license '{}', expected one of: {}",
+            id,
+            ids.join(", ")
+        )
+    })
+}
+
+/// Fills in the copyright year and holder placeholders of a license text
+pub fn render(text: &str, holder: &str, year: i64) -> String {
+    let year = year.to_string();
+    text.replace("[yyyy]", &year)
+        .replace("[year]", &year)
+        .replace("[name of copyright owner]", holder)
+        .replace("[fullname]", holder)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find_resolves_ids_and_aliases_ignoring_case() {
+        assert_eq!(find("GPL-3.0").map(|l| l.id), Some("GPL-3.0-only"));
+        assert_eq!(find("gpl-3.0").map(|l| l.id), Some("GPL-3.0-only"));
+        assert_eq!(find("gpl-3.0+").map(|l| l.id), Some("GPL-3.0-or-later"));
+        assert_eq!(find("mit").map(|l| l.id), Some("MIT"));
+        assert_eq!(find("apache").map(|l| l.expression), Some("Apache-2.0"));
+        assert!(find("WTFPL").is_none());
+    }
+
+    #[test]
+    fn lookup_lists_supported_licenses() {
+        let err = lookup("WTFPL").err().unwrap().to_string();
+        assert_eq!(
+            err,
+            "Unsupported license 'WTFPL', expected one of: MIT, BSD-3-Clause, Apache-2.0, \
+             GPL-3.0-only, GPL-3.0-or-later, proprietary"
+        );
+    }
+
+    #[test]
+    fn render_fills_every_placeholder() {
+        let text = "Copyright [year] [fullname]\nCopyright [yyyy] [name of copyright owner]\n";
+        assert_eq!(
+            render(text, "Ada Lovelace", 2026),
+            "Copyright 2026 Ada Lovelace\nCopyright 2026 Ada Lovelace\n"
+        );
+    }
+}
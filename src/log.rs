@@ -0,0 +1,228 @@
+use anyhow::{Context, Result};
+use std::env;
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::config::xdg_dir;
+use crate::pipeline::ensure_project_dir;
+
+/// Record of every external command and HTTP request made during a run.
+///
+/// Entries are appended as they happen, so the log survives a failed run even
+/// though the project directory itself is rolled back.
+#[derive(Clone)]
+pub struct RunLog {
+    inner: Option<Arc<LogFile>>,
+}
+
+struct LogFile {
+    path: PathBuf,
+    file: Mutex<File>,
+}
+
+impl RunLog {
+    /// Opens `path`, or a timestamped file in the user's log directory when no path is given
+    pub fn create(path: Option<PathBuf>) -> Result<Self> {
+        let path = match path {
+            Some(path) => path,
+            None => {
+                let dir = log_dir().context("Could not determine the log directory")?;
+                std::fs::create_dir_all(&dir)
+                    .with_context(|| format!("Failed to create {}", dir.display()))?;
+                dir.join(file_name())
+            }
+        };
+        let mut file = File::create(&path)
+            .with_context(|| format!("Failed to create log file {}", path.display()))?;
+        let args: Vec<String> = env::args().collect();
+        writeln!(
+            file,
+            "# {} {}",
+            timestamp(SystemTime::now()),
+            args.join(" ")
+        )
+        .with_context(|| format!("Failed to write log file {}", path.display()))?;
+        Ok(Self {
+            inner: Some(Arc::new(LogFile {
+                path,
+                file: Mutex::new(file),
+            })),
+        })
+    }
+
+    /// A log that records nothing, used for dry runs
+    pub fn disabled() -> Self {
+        Self { inner: None }
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.inner.as_ref().map(|log| log.path.as_path())
+    }
+
+    /// Records a finished external command; `exit_code` is `None` if it was killed by a signal
+    pub fn command(
+        &self,
+        command: &str,
+        cwd: &Path,
+        exit_code: Option<i32>,
+        duration: Duration,
+        stdout: &str,
+        stderr: &str,
+    ) {
+        let mut entry = format!("$ {}\ncwd: {}\n", command, cwd.display());
+        match exit_code {
+            Some(code) => writeln!(entry, "exit code: {}", code),
+            None => writeln!(entry, "exit code: none (terminated by signal)"),
+        }
+        .expect("writing to a String cannot fail");
+        push_section(&mut entry, "stdout", stdout);
+        push_section(&mut entry, "stderr", stderr);
+        self.append(duration, entry);
+    }
+
+    /// Records a command that could not be started at all
+    pub fn spawn_error(&self, command: &str, cwd: &Path, error: &std::io::Error) {
+        self.append(
+            Duration::ZERO,
+            format!(
+                "$ {}\ncwd: {}\nfailed to start: {}\n",
+                command,
+                cwd.display(),
+                error
+            ),
+        );
+    }
+
+    /// Records an HTTP request with its status, or the error if no response arrived
+    pub fn http(
+        &self,
+        method: &str,
+        url: &str,
+        outcome: Result<u16, &anyhow::Error>,
+        duration: Duration,
+    ) {
+        let result = match outcome {
+            Ok(status) => format!("status: {}", status),
+            Err(err) => format!("error: {:#}", err),
+        };
+        self.append(duration, format!("{} {}\n{}\n", method, url, result));
+    }
+
+    /// Copies the log into the project's `.pycargo/` directory and returns the copy's path
+    pub fn copy_into(&self, project_dir: &Path) -> Result<Option<PathBuf>> {
+        let Some(log) = &self.inner else {
+            return Ok(None);
+        };
+        let dir = ensure_project_dir(project_dir)?;
+        let name = log.path.file_name().context("Log file has no name")?;
+        let copy = dir.join(name);
+        std::fs::copy(&log.path, &copy)
+            .with_context(|| format!("Failed to copy log to {}", copy.display()))?;
+        Ok(Some(copy))
+    }
+
+    fn append(&self, duration: Duration, entry: String) {
+        let Some(log) = &self.inner else {
+            return;
+        };
+        let mut file = log.file.lock().expect("log file lock poisoned");
+        // Losing a log line must never fail the setup itself
+        let _ = writeln!(
+            file,
+            "\n[{}] ({} ms)\n{}",
+            timestamp(SystemTime::now()),
+            duration.as_millis(),
+            entry.trim_end()
+        );
+    }
+}
+
+fn push_section(entry: &mut String, name: &str, text: &str) {
+    if text.trim().is_empty() {
+        return;
+    }
+    writeln!(entry, "--- {} ---\n{}", name, text.trim_end())
+        .expect("writing to a String cannot fail");
+}
+
+/// Location of run logs, honouring `XDG_STATE_HOME`
+fn log_dir() -> Option<PathBuf> {
+    Some(xdg_dir("XDG_STATE_HOME", ".local/state", "LOCALAPPDATA")?.join("logs"))
+}
+
+/// `pycargo-20240131T120000Z-1234.log`; the process id keeps parallel runs apart
+fn file_name() -> String {
+    let stamp: String = timestamp(SystemTime::now())
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect();
+    format!("pycargo-{}-{}.log", stamp, std::process::id())
+}
+
+/// Formats a time as an RFC 3339 UTC timestamp such as `2024-01-31T12:00:00Z`
+fn timestamp(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let (days, rem) = (secs / 86_400, secs % 86_400);
+    let (year, month, day) = civil_date(days);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        rem / 3_600,
+        rem % 3_600 / 60,
+        rem % 60
+    )
+}
+
+/// The current year in UTC, used in license copyright lines
+pub fn current_year() -> i64 {
+    let days = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() / 86_400)
+        .unwrap_or_default();
+    civil_date(days).0
+}
+
+/// Year, month and day of a count of days since 1970-01-01
+fn civil_date(days: u64) -> (i64, i64, i64) {
+    // Civil-from-days conversion for the proleptic Gregorian calendar
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn civil_date_handles_leap_years() {
+        assert_eq!(civil_date(0), (1970, 1, 1));
+        assert_eq!(civil_date(11_016), (2000, 2, 29));
+        assert_eq!(civil_date(11_017), (2000, 3, 1));
+        assert_eq!(civil_date(20_454), (2026, 1, 1));
+    }
+
+    #[test]
+    fn timestamp_is_rfc3339_utc() {
+        let time = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        assert_eq!(timestamp(time), "2023-11-14T22:13:20Z");
+    }
+}
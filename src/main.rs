@@ -1,366 +1,1337 @@
+mod cache;
+mod cli;
+mod config;
+mod ctx;
+mod doctor;
+mod gitignore;
+mod http;
+mod license;
+mod log;
+mod output;
+mod pipeline;
+mod preflight;
+mod shell;
+mod source;
+mod templates;
+
 use anyhow::{Context, Result};
-use clap::Parser;
+use clap::{Parser, ValueEnum};
+use cli::{
+    Cli, Commands, GitIdentityArgs, GlobalArgs, InitArgs, NewArgs, PrefetchArgs, ResumeArgs,
+    TemplatesCommand,
+};
 use colored::*;
-use indicatif::{ProgressBar, ProgressStyle};
+use config::{Config, ProjectSettings};
+use ctx::Ctx;
+use log::RunLog;
+use output::Output;
+use pipeline::{Kind, State, Step};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use shell::Shell;
 use std::env;
-use std::io;
-use std::time::Duration;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use templates::SetupType;
 use tokio::fs;
-use tokio::process::Command;
-
-const GITIGNORE_URL: &str =
-    "https://raw.githubusercontent.com/github/gitignore/main/Python.gitignore";
-const LICENSE_URL: &str = "https://www.apache.org/licenses/LICENSE-2.0.txt";
-
-// Embed the template files into the binary using `include_str!`
-const BASIC_TEMPLATE: &str = include_str!("../templates/basic.txt");
-const ADVANCED_TEMPLATE: &str = include_str!("../templates/advanced.txt");
-const DATASCIENCE_TEMPLATE: &str = include_str!("../templates/datascience.txt");
-
-/// PyCargo – Bootstrap a Python Data Science Project
-#[derive(Parser)]
-#[command(author, version, about)]
-struct Args {
-    /// Name of the project directory
-    #[arg(short, long)]
-    name: String,
 
-    /// Flag to indicate if a GitHub repo should be created
-    #[arg(short = 'g', long)]
-    github_repo: bool,
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let out = Output::new(cli.global.output, cli.global.verbose);
+    if let Err(err) = http::init(cli.global.ca_bundle.as_deref()) {
+        // The doctor reports a broken CA bundle instead of refusing to run
+        if !matches!(cli.command, Commands::Doctor) {
+            return Err(err);
+        }
+        http::init(None)?;
+    }
 
-    /// Optional custom name for the GitHub repo
-    #[arg(long, value_name = "GITHUB_REPO_NAME")]
-    github_repo_name: Option<String>,
+    match cli.command {
+        Commands::New(args) => out.finish(new_project(args, cli.global, &out).await),
+        Commands::Init(args) => out.finish(init_project(args, cli.global, &out).await),
+        Commands::Resume(args) => out.finish(resume_project(args, cli.global, &out).await),
+        Commands::Prefetch(args) => prefetch(args, cli.global, &out).await,
+        Commands::Shell(args) => shell::run(args, &out).await,
+        Commands::Doctor => {
+            let log = open_log(&cli.global)?;
+            let result = doctor::run(cli.global.ca_bundle.as_deref(), &log, &out).await;
+            if result.is_err() {
+                print_log_location(&out, &log);
+            }
+            result
+        }
+        Commands::Templates(TemplatesCommand::List) => list_templates(&out),
+        Commands::Config(command) => config::run(command, &out),
+    }
+}
 
-    /// Setup type: basic, advanced, data-science, or blank
-    #[arg(short = 's', long, default_value = "advanced")]
-    setup: String,
+/// Creates a new project directory and runs the full setup inside it.
+///
+/// The project is built in a hidden staging directory next to the target and
+/// only renamed into place once every step has succeeded. A failed step
+/// leaves the staging directory behind for `pycargo resume`; on Ctrl-C the
+/// staging directory and any GitHub repository created so far are removed.
+async fn new_project(args: NewArgs, global: GlobalArgs, out: &Output) -> Result<Option<Summary>> {
+    let dry_run = global.dry_run;
+    print_dry_run_banner(out, dry_run);
+    out.header("📁", "Project Setup");
+
+    let config = Config::load()?;
+    let mut settings = ProjectSettings::for_new(&args, &config);
+    if global.offline
+        && let Some(github) = settings.github.take()
+    {
+        out.warn(format!(
+            "Offline: skipping the GitHub repository '{}'. Run without --offline to create one",
+            github.name
+        ));
+    }
+    let log = open_log(&global)?;
+    preflight::check_new(&args, &settings, &global, &log, out).await?;
+
+    let project_dir = PathBuf::from(&args.name);
+    let staging_dir = staging_dir_for(&project_dir)?;
+    if !dry_run && fs::metadata(&staging_dir).await.is_ok() {
+        out.warn(format!(
+            "Removing leftover staging directory from a previous run: {}",
+            staging_dir.display()
+        ));
+        fs::remove_dir_all(&staging_dir)
+            .await
+            .context("Failed to remove leftover staging directory")?;
+    }
 
-    /// Specify if the GitHub repository should be private
-    #[arg(short = 'p', long)]
-    private: bool,
+    let state = State::new(
+        Kind::New,
+        project_dir,
+        settings,
+        args.project.identity.clone(),
+    );
+    let ctx = Arc::new(Ctx::new(&staging_dir, &global, out, &log));
+    finish_new_project(ctx, state, &log, args.project.all_shells).await
 }
 
-fn spinner_style() -> ProgressStyle {
-    ProgressStyle::default_spinner()
-        .tick_strings(&["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"])
-        .template("{spinner} {msg}")
-        .expect("Failed to set spinner template")
+/// Runs the remaining steps of a `new` setup in its staging directory and moves the result into place
+async fn finish_new_project(
+    ctx: Arc<Ctx>,
+    mut state: State,
+    log: &RunLog,
+    all_shells: bool,
+) -> Result<Option<Summary>> {
+    let out = &ctx.out;
+    let mut build = tokio::spawn({
+        let ctx = Arc::clone(&ctx);
+        async move {
+            pipeline::run(&ctx, &mut state).await?;
+            Ok(state)
+        }
+    });
+
+    let (result, interrupted) = tokio::select! {
+        joined = &mut build => (joined.context("Project setup task failed").and_then(|r| r), false),
+        _ = tokio::signal::ctrl_c() => (Err(anyhow::anyhow!("Setup interrupted by Ctrl-C")), true),
+    };
+    if interrupted {
+        // Stop the steps before rolling back, so none of them recreates what is being removed
+        build.abort();
+        let _ = build.await;
+    }
+
+    let state = match result {
+        Ok(state) => state,
+        Err(err) => {
+            if interrupted {
+                rollback(&ctx).await;
+                print_log_location(out, log);
+                // A pending prompt may still be blocking a runtime thread, so exit directly
+                let _ = out.finish::<Summary>(Err(err));
+                out.error("Setup interrupted by Ctrl-C");
+                std::process::exit(130);
+            }
+            print_resume_hint(&ctx, log);
+            return Err(err);
+        }
+    };
+
+    let project_dir = &state.project_dir;
+    if let Err(err) = ctx.rename(&ctx.root, project_dir).await {
+        // Every step is done; resuming retries the move
+        print_resume_hint(&ctx, log);
+        return Err(err);
+    }
+    ctx.success(format!(
+        "Moved project into place: {}",
+        project_dir.display()
+    ));
+    if ctx.dry_run {
+        print_dry_run_complete(out);
+        return Ok(None);
+    }
+
+    // Collected first, so the commands it runs are in the project's copy of the log
+    let summary = Summary::collect(out, log, project_dir, state.remote_url).await;
+    save_log(out, log, project_dir);
+    print_completion(out, Some(project_dir), all_shells);
+    Ok(Some(summary))
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    let args = Args::parse();
+/// Returns the hidden sibling directory a project is staged in before being moved into place
+fn staging_dir_for(project_dir: &Path) -> Result<PathBuf> {
+    let name = project_dir
+        .file_name()
+        .with_context(|| format!("Invalid project name: {}", project_dir.display()))?;
+    Ok(project_dir.with_file_name(format!(".{}.pycargo-tmp", name.to_string_lossy())))
+}
 
-    // Early exit for version flag
-    if std::env::args().any(|arg| arg == "--version") {
-        return Ok(());
+/// Undoes the side effects of an interrupted or aborted `new` run, reporting anything left behind
+async fn rollback(ctx: &Ctx) {
+    if ctx.dry_run {
+        return;
+    }
+
+    let out = &ctx.out;
+    out.header("↩️", "Rolling Back");
+
+    if let Some(full_name) = ctx.take_github_repo() {
+        let deleted = delete_github_repo(ctx, &full_name).await;
+        out.event(
+            "rollback",
+            serde_json::json!({
+                "github_repo": full_name,
+                "removed": deleted.is_ok(),
+            }),
+        );
+        match deleted {
+            Ok(()) => out.success(format!("Deleted GitHub repository '{}'", full_name)),
+            Err(err) => out.warn(format!(
+                "GitHub repository '{}' was left behind ({:#}). Delete it at https://github.com/{}/settings",
+                full_name, err, full_name
+            )),
+        }
+    }
+
+    if fs::metadata(&ctx.root).await.is_ok() {
+        let removed = fs::remove_dir_all(&ctx.root).await;
+        out.event(
+            "rollback",
+            serde_json::json!({
+                "staging_dir": ctx.root,
+                "removed": removed.is_ok(),
+            }),
+        );
+        match removed {
+            Ok(()) => out.success(format!("Removed staging directory {}", ctx.root.display())),
+            Err(err) => out.warn(format!(
+                "Staging directory {} was left behind: {}",
+                ctx.root.display(),
+                err
+            )),
+        }
     }
+}
 
-    println!("{}", "=== 📁 Project Setup ===".bold().blue());
+/// Bootstraps an existing directory, leaving files that are already present untouched
+async fn init_project(args: InitArgs, global: GlobalArgs, out: &Output) -> Result<Option<Summary>> {
+    let dry_run = global.dry_run;
+    print_dry_run_banner(out, dry_run);
+    out.header("📁", "Project Setup");
+
+    let config = Config::load()?;
+    let settings = ProjectSettings::for_init(&args, &config);
+    let log = open_log(&global)?;
+    preflight::check_init(&args, &settings, &global, &log, out).await?;
+
+    let path = &args.path;
+    let state = State::new(
+        Kind::Init,
+        path.clone(),
+        settings,
+        args.project.identity.clone(),
+    );
+    let ctx = Ctx::new(path, &global, out, &log);
+    ctx.success(format!("Using existing directory: {}", path.display()));
+    finish_init_project(&ctx, state, &log, args.project.all_shells).await
+}
 
-    let project_name = &args.name;
-    if fs::metadata(project_name).await.is_ok() {
+/// Runs the remaining steps of an `init` setup
+async fn finish_init_project(
+    ctx: &Ctx,
+    mut state: State,
+    log: &RunLog,
+    all_shells: bool,
+) -> Result<Option<Summary>> {
+    let out = &ctx.out;
+    if let Err(err) = pipeline::run(ctx, &mut state).await {
+        print_resume_hint(ctx, log);
+        return Err(err);
+    }
+    if ctx.dry_run {
+        print_dry_run_complete(out);
+        return Ok(None);
+    }
+
+    let summary = Summary::collect(out, log, &ctx.root, None).await;
+    save_log(out, log, &ctx.root);
+    // No need to change directory when bootstrapping the current one
+    let project_dir = Some(ctx.root.as_path()).filter(|dir| *dir != Path::new("."));
+    print_completion(out, project_dir, all_shells);
+    Ok(Some(summary))
+}
+
+/// Continues an unfinished `new` or `init` setup from its failed step, or discards it
+async fn resume_project(
+    args: ResumeArgs,
+    global: GlobalArgs,
+    out: &Output,
+) -> Result<Option<Summary>> {
+    let (root, state) = find_unfinished_setup(&args.path)?;
+    let log = if args.abort {
+        RunLog::disabled()
+    } else {
+        open_log(&global)?
+    };
+    let ctx = Ctx::new(&root, &global, out, &log);
+    if let Some(full_name) = &state.github_repo {
+        ctx.record_github_repo(full_name);
+    }
+
+    if args.abort {
+        match state.kind {
+            Kind::New => rollback(&ctx).await,
+            // Files added to an existing directory are kept, only the saved progress is dropped
+            Kind::Init => {
+                let path = State::discard(&root)?;
+                ctx.success(format!("Discarded saved progress {}", path.display()));
+            }
+        }
+        return Ok(None);
+    }
+
+    if global.offline
+        && state.settings.github.is_some()
+        && !state.completed.contains(&Step::GithubRemote)
+    {
         anyhow::bail!(
             "{}",
-            format!("❌ Directory '{}' already exists", project_name).red()
+            out.failure(
+                "This setup still has to create its GitHub repository. Resume without --offline"
+            )
         );
     }
 
-    // Create project directory
-    fs::create_dir(project_name).await?;
-    println!(
-        "{}",
-        format!("✅ Created project directory: {}", project_name).green()
-    );
+    print_dry_run_banner(out, ctx.dry_run);
+    out.header("📁", "Project Setup");
+    if !state.failed.is_empty() {
+        let failed: Vec<&str> = state.failed.iter().map(|step| step.id()).collect();
+        out.info(format!("Resuming from failed step {}", failed.join(", ")));
+    }
+    match state.kind {
+        Kind::New => finish_new_project(Arc::new(ctx), state, &log, args.all_shells).await,
+        Kind::Init => finish_init_project(&ctx, state, &log, args.all_shells).await,
+    }
+}
 
-    // Check Git configuration
-    check_git_config("user.name", "name").await?;
-    check_git_config("user.email", "email").await?;
+/// Locates the saved state for `path`: a `new` staging directory first, then the directory itself.
+///
+/// A `new` setup whose steps all completed but which is still staged only has
+/// to be moved into place, so it counts as unfinished. Its target is `path`,
+/// resolved from where `resume` runs rather than where `new` ran.
+fn find_unfinished_setup(path: &Path) -> Result<(PathBuf, State)> {
+    let mut candidates = Vec::new();
+    if path.file_name().is_some() {
+        candidates.push((staging_dir_for(path)?, true));
+    }
+    candidates.push((path.to_path_buf(), false));
+
+    for (root, staged) in candidates {
+        if let Some(mut state) = State::load(&root)? {
+            if state.is_finished() && !staged {
+                anyhow::bail!("The setup of {} already completed", path.display());
+            }
+            if staged {
+                state.project_dir = path.to_path_buf();
+            }
+            return Ok((root, state));
+        }
+    }
+    anyhow::bail!("No unfinished pycargo setup found for {}", path.display())
+}
 
-    // Check dependencies
-    check_uv_installation().await?;
+fn print_resume_hint(ctx: &Ctx, log: &RunLog) {
+    print_log_location(&ctx.out, log);
+    if ctx.dry_run {
+        return;
+    }
+    if let Ok(Some(state)) = State::load(&ctx.root) {
+        ctx.out.info(format!(
+            "Progress was saved. Continue with `pycargo resume {0}` or undo with `pycargo resume {0} --abort`",
+            state.project_dir.display()
+        ));
+    }
+}
 
-    println!("\n{}", "=== 🚀 Environment Setup ===".bold().blue());
+/// Opens the command log for a run; dry runs execute nothing and are not logged
+fn open_log(global: &GlobalArgs) -> Result<RunLog> {
+    if global.dry_run {
+        Ok(RunLog::disabled())
+    } else {
+        RunLog::create(global.log_file.clone())
+    }
+}
 
-    // Change into project directory
-    env::set_current_dir(project_name)?;
+fn print_log_location(out: &Output, log: &RunLog) {
+    if let Some(path) = log.path() {
+        out.info(format!("Full command log: {}", path.display()));
+    }
+}
 
-    // Setup environment
-    setup_environment().await?;
-    println!("{}", "Activate with: .venv\\Scripts\\activate".yellow());
+/// Keeps a copy of the command log in the project's `.pycargo/` directory
+fn save_log(out: &Output, log: &RunLog, project_dir: &Path) {
+    match log.copy_into(project_dir) {
+        Ok(Some(copy)) => out.info(format!("Command log saved to {}", copy.display())),
+        Ok(None) => {}
+        Err(err) => out.warn(format!("{:#}", err)),
+    }
+}
 
-    // Setup requirements.txt
-    create_requirements_file(&args.setup).await?;
+fn print_dry_run_banner(out: &Output, dry_run: bool) {
+    if dry_run {
+        out.highlight(
+            "🔍",
+            "Dry run: listing every action without performing it",
+            Color::Cyan,
+        );
+    }
+}
 
-    println!("\n{}", "=== 📦 File Downloads ===".bold().blue());
+fn print_dry_run_complete(out: &Output) {
+    out.print("");
+    out.highlight("🔍", "Dry run complete, nothing was changed", Color::Cyan);
+}
 
-    // Download additional files
-    download_and_write_file(GITIGNORE_URL, ".gitignore").await?;
-    download_and_write_file(LICENSE_URL, "LICENSE").await?;
+fn print_completion(out: &Output, project_dir: Option<&Path>, all_shells: bool) {
+    out.print("");
+    out.highlight("✅🐍", "Setup Completed", Color::Green);
+    out.print("");
+    out.print("To activate the virtual environment, run:".bold().blue());
+    if let Some(dir) = project_dir {
+        out.info(format!("cd {}", dir.display()));
+    }
+    if all_shells {
+        let activations = shell::all_activations();
+        let width = activations
+            .iter()
+            .map(|(label, _)| label.len())
+            .max()
+            .unwrap_or(0)
+            + 1;
+        for (label, command) in activations {
+            out.info(format!(
+                "{:<width$}  {}",
+                format!("{}:", label),
+                command,
+                width = width
+            ));
+        }
+    } else {
+        out.info(Shell::detect().activate_command(cfg!(windows)));
+    }
+}
 
-    println!("\n{}", "=== 🔧 Git Setup ===".bold().blue());
+/// Result of a successful run, reported as the final JSON event
+#[derive(Serialize)]
+struct Summary {
+    project_path: PathBuf,
+    remote_url: Option<String>,
+    python_version: Option<String>,
+    packages: Vec<InstalledPackage>,
+}
 
-    // Initialize Git
-    initialize_git_repo().await?;
-    println!(
-        "{}",
-        "Files: .gitignore, LICENSE, README.md, main.py, etc.".yellow()
-    );
+#[derive(Serialize, Deserialize)]
+struct InstalledPackage {
+    name: String,
+    version: String,
+}
+
+impl Summary {
+    /// Inspects the finished project; the environment is only queried when JSON is requested
+    async fn collect(
+        out: &Output,
+        log: &RunLog,
+        project_dir: &Path,
+        remote_url: Option<String>,
+    ) -> Self {
+        let project_path =
+            std::path::absolute(project_dir).unwrap_or_else(|_| project_dir.to_path_buf());
+        let (python_version, packages) = if out.is_json() {
+            (
+                venv_python_version(log, project_dir).await,
+                installed_packages(log, project_dir).await,
+            )
+        } else {
+            (None, Vec::new())
+        };
+        Self {
+            project_path,
+            remote_url,
+            python_version,
+            packages,
+        }
+    }
+}
+
+/// Version of the interpreter in the project's `.venv`, e.g. `3.12.4`
+async fn venv_python_version(log: &RunLog, project_dir: &Path) -> Option<String> {
+    let python = if cfg!(windows) {
+        project_dir.join(".venv").join("Scripts").join("python.exe")
+    } else {
+        project_dir.join(".venv").join("bin").join("python")
+    };
+    let version = tool_version(log, &python.to_string_lossy()).await?;
+    Some(version.trim_start_matches("Python ").to_string())
+}
 
-    // Handle GitHub integration
-    if args.github_repo {
-        let repo_name = args
-            .github_repo_name
-            .clone()
-            .unwrap_or_else(|| project_name.clone());
-
-        validate_env_vars()?;
-        create_github_repo(&repo_name, args.private).await?;
-        let remote_url = setup_github_remote(&repo_name).await?;
-        println!(
+/// Packages installed in the project's environment, as reported by `uv pip list`
+async fn installed_packages(log: &RunLog, project_dir: &Path) -> Vec<InstalledPackage> {
+    match ctx::query(log, project_dir, "uv", &["pip", "list", "--format", "json"]).await {
+        Ok(output) if output.status.success() => {
+            serde_json::from_slice(&output.stdout).unwrap_or_default()
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Fills uv's package cache and the download cache so that `--offline` setups of a template work
+async fn prefetch(args: PrefetchArgs, global: GlobalArgs, out: &Output) -> Result<()> {
+    if global.offline {
+        anyhow::bail!(
             "{}",
+            out.failure("pycargo prefetch needs network access and cannot run with --offline")
+        );
+    }
+    print_dry_run_banner(out, global.dry_run);
+    out.header("📦", "Prefetch");
+
+    let config = Config::load()?;
+    let settings = ProjectSettings::for_prefetch(&args, &config);
+    if let Some(version) = &settings.python
+        && requires_python(version).is_none()
+    {
+        anyhow::bail!(
+            "{}",
+            out.failure(format!("'{}' is not a valid Python version", version))
+        );
+    }
+
+    // Packages are resolved in a throwaway project, the same way `uv add` resolves them during setup
+    let root = env::temp_dir().join(format!("pycargo-prefetch-{}", std::process::id()));
+    let log = open_log(&global)?;
+    let ctx = Ctx::new(&root, &global, out, &log);
+    let result = prefetch_into(&ctx, &settings).await;
+    if !ctx.dry_run
+        && fs::metadata(&root).await.is_ok()
+        && let Err(err) = fs::remove_dir_all(&root).await
+    {
+        out.warn(format!("Failed to remove {}: {}", root.display(), err));
+    }
+    if result.is_err() {
+        print_log_location(out, &log);
+    }
+    result?;
+
+    if ctx.dry_run {
+        print_dry_run_complete(out);
+    } else {
+        out.print("");
+        out.highlight(
+            "✅📦",
             format!(
-                "✅ GitHub repository created: {}",
-                remote_url.trim_end_matches(".git")
-            )
-            .green()
+                "Caches are ready for `--offline` setups of the {} template",
+                settings.setup
+            ),
+            Color::Green,
         );
     }
+    Ok(())
+}
 
-    println!("\n{}", "✅ Setup Completed 🐍".bold().green());
+async fn prefetch_into(ctx: &Ctx, settings: &ProjectSettings) -> Result<()> {
+    check_uv_installation(ctx).await?;
+    let python = settings.python.as_deref();
+    if let Some(version) = python {
+        install_python(ctx, version).await?;
+    }
 
-    println!(
-        "\n{}",
-        "To activate the virtual environment, run:".bold().blue()
-    );
-    println!("{}", ".venv\\Scripts\\activate".yellow());
+    let packages = async {
+        let step = ctx.step(
+            "prefetch_packages",
+            format!(
+                "Downloading packages for the {} template...",
+                settings.setup
+            ),
+        );
+        let requirements = settings.setup.load_requirements(&settings.template_dirs)?;
+        if templates::packages(&requirements).is_empty() {
+            step.skip("The template has no packages to download");
+            return Ok(());
+        }
+        ctx.create_dir(&ctx.root).await?;
+        ctx.uv_command(&with_python(
+            &["init", ".", "--name", "pycargo-prefetch", "--vcs", "none"],
+            python,
+        ))
+        .await?;
+        ctx.write("requirements.txt", requirements).await?;
+        ctx.uv_command(&["add", "-r", "requirements.txt"]).await?;
+        step.finish("Packages cached by uv");
+        anyhow::Ok(())
+    };
+    let downloads = async {
+        let step = ctx.step("prefetch_downloads", "Caching .gitignore and LICENSE...");
+        for section in &settings.gitignore {
+            download_file(ctx, &settings.gitignore_location(*section)).await?;
+        }
+        if let Some(location) = settings.license_location()? {
+            download_file(ctx, &location).await?;
+        }
+        step.finish("Cached .gitignore and LICENSE");
+        anyhow::Ok(())
+    };
+    tokio::try_join!(packages, downloads)?;
+    Ok(())
+}
 
+/// Prints each setup type along with the packages it installs
+fn list_templates(out: &Output) -> Result<()> {
+    let template_dirs = Config::load()?.template_dirs.unwrap_or_default();
+
+    out.header("📋", "Available Templates");
+    for setup in SetupType::value_variants() {
+        let requirements = setup.load_requirements(&template_dirs)?;
+        let packages = templates::packages(&requirements);
+        match setup.find_override(&template_dirs) {
+            Some(path) => out.data(format!(
+                "{} {}",
+                setup.to_string().bold().green(),
+                format!("(from {})", path.display()).blue()
+            )),
+            None => out.data(setup.to_string().bold().green()),
+        }
+        if packages.is_empty() {
+            out.data(format!("  {}", "(empty requirements.txt)".yellow()));
+        } else {
+            out.data(format!("  {}", packages.join(", ").yellow()));
+        }
+    }
+    Ok(())
+}
+
+async fn check_uv_installation(ctx: &Ctx) -> Result<()> {
+    if ctx.dry_run {
+        ctx.plan("run `uv --version`, installing uv with `pip install uv` if it is missing");
+        return Ok(());
+    }
+
+    let step = ctx.step("check_uv", "Checking uv installation...");
+
+    match tool_version(&ctx.log, "uv").await {
+        Some(version) => step.finish(format!("uv is already installed ({})", version)),
+        None if ctx.offline => anyhow::bail!(
+            "{}",
+            ctx.out
+                .failure("uv is not installed and cannot be installed with --offline")
+        ),
+        None => {
+            ctx.out.print("uv not found. Installing uv...");
+            ctx.run_global("pip", &["install", "uv"]).await?;
+            step.finish("uv installed");
+        }
+    }
+    Ok(())
+}
+
+/// Runs `<cmd> --version` and returns the first line it prints, or `None` if it cannot run
+async fn tool_version(log: &RunLog, cmd: &str) -> Option<String> {
+    let output = ctx::query(log, Path::new("."), cmd, &["--version"])
+        .await
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    // Older Python versions print their version to stderr
+    let text = if output.stdout.is_empty() {
+        output.stderr
+    } else {
+        output.stdout
+    };
+    String::from_utf8_lossy(&text)
+        .lines()
+        .next()
+        .map(|line| line.trim().to_string())
+}
+
+async fn create_project_dir(ctx: &Ctx, project_dir: &Path) -> Result<()> {
+    let step = ctx.step("create_dir", "Creating project directory...");
+    ctx.create_dir(&ctx.root).await?;
+    step.finish(format!(
+        "Created project directory: {}",
+        project_dir.display()
+    ));
+    Ok(())
+}
+
+async fn uv_init(ctx: &Ctx, python: Option<&str>) -> Result<()> {
+    let step = ctx.step("uv_init", "Initializing uv...");
+    if ctx.exists("pyproject.toml").await {
+        step.skip("pyproject.toml already exists, skipping uv init");
+    } else {
+        // The Git step creates the repository itself, with the configured branch
+        ctx.uv_command(&with_python(&["init", ".", "--vcs", "none"], python))
+            .await?;
+        step.finish("uv initialized");
+    }
     Ok(())
 }
 
-async fn check_uv_installation() -> Result<()> {
-    let spinner = ProgressBar::new_spinner();
-    spinner.set_style(spinner_style());
-    spinner.set_message("Checking uv installation...");
-    spinner.enable_steady_tick(Duration::from_millis(100));
+/// Fills the empty README created by `uv init` with activation instructions for every shell
+async fn write_readme(ctx: &Ctx, project_dir: &Path) -> Result<()> {
+    let step = ctx.step("write_readme", "Writing README.md...");
+    let existing = fs::read_to_string(ctx.path("README.md"))
+        .await
+        .unwrap_or_default();
+    if !existing.trim().is_empty() {
+        step.skip("README.md already has content, leaving it unchanged");
+        return Ok(());
+    }
+
+    let name = std::path::absolute(project_dir)
+        .ok()
+        .and_then(|dir| {
+            dir.file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+        })
+        .unwrap_or_else(|| "Project".to_string());
+    ctx.write(
+        "README.md",
+        format!("# {}\n\n{}", name, shell::readme_section()),
+    )
+    .await?;
+    step.finish("README.md written with activation instructions");
+    Ok(())
+}
 
-    if run("uv", &["--version"]).await.is_err() {
-        spinner.finish_and_clear();
-        println!("uv not found. Installing uv...");
-        run("pip", &["install", "uv"]).await?;
-        println!("{}", "✅ uv installed".green());
+async fn create_venv(ctx: &Ctx, python: Option<&str>) -> Result<()> {
+    let step = ctx.step("create_venv", "Creating virtual environment...");
+    if ctx.exists(".venv").await {
+        step.skip(".venv already exists, skipping virtual environment creation");
     } else {
-        spinner.finish_and_clear();
-        println!("{}", "✅ uv is already installed".green());
+        // Relocatable so the environment survives the move out of the staging directory
+        ctx.uv_command(&with_python(&["venv", ".venv", "--relocatable"], python))
+            .await?;
+        step.finish("virtual environment created");
     }
+
     Ok(())
 }
 
-async fn setup_environment() -> Result<()> {
-    let spinner = ProgressBar::new_spinner();
-    spinner.set_style(spinner_style());
-    spinner.set_message("Initializing uv...");
-    spinner.enable_steady_tick(Duration::from_millis(100));
-    run("uv", &["init", "."]).await?;
-    spinner.finish_and_clear();
-    println!("{}", "✅ uv initialized".green());
+/// Installs a uv-managed Python unless an interpreter matching `version` is already available
+async fn install_python(ctx: &Ctx, version: &str) -> Result<()> {
+    if ctx.dry_run {
+        ctx.plan(format!(
+            "run `uv python find {0}`, running `uv python install {0}` if it is missing",
+            version
+        ));
+        return Ok(());
+    }
 
-    let spinner = ProgressBar::new_spinner();
-    spinner.set_style(spinner_style());
-    spinner.set_message("Creating virtual environment...");
-    spinner.enable_steady_tick(Duration::from_millis(100));
-    run("uv", &["venv", ".venv"]).await?;
-    spinner.finish_and_clear();
-    println!("{}", "✅ virtual environment created".green());
+    let step = ctx.step(
+        "install_python",
+        format!("Looking for Python {}...", version),
+    );
+    let found = ctx::query(&ctx.log, Path::new("."), "uv", &["python", "find", version])
+        .await
+        .is_ok_and(|output| output.status.success());
+    if found {
+        step.skip(format!("Python {} is already available", version));
+    } else {
+        ctx.uv_global_command(&["python", "install", version])
+            .await?;
+        step.finish(format!("Python {} installed", version));
+    }
+    Ok(())
+}
 
+/// Pins `version` in `.python-version` and sets `requires-python` in pyproject.toml to match
+async fn pin_python(ctx: &Ctx, version: &str) -> Result<()> {
+    let step = ctx.step("pin_python", format!("Pinning Python {}...", version));
+    let spec = requires_python(version)
+        .with_context(|| format!("Invalid Python version '{}'", version))?;
+    ctx.uv_command(&["python", "pin", version]).await?;
+
+    if ctx.dry_run {
+        ctx.plan(format!(
+            "set requires-python = \"{}\" in pyproject.toml",
+            spec
+        ));
+        step.finish("Python pinned");
+        return Ok(());
+    }
+    let pyproject = fs::read_to_string(ctx.path("pyproject.toml"))
+        .await
+        .context("Failed to read pyproject.toml")?;
+    match set_project_key(&pyproject, "requires-python", &spec, true) {
+        Some(updated) if updated != pyproject => ctx.write("pyproject.toml", updated).await?,
+        Some(_) => {}
+        None => ctx
+            .out
+            .warn("pyproject.toml has no [project] table, requires-python was not set"),
+    }
+    step.finish(format!(
+        "Python {} pinned in .python-version, requires-python = \"{}\"",
+        version, spec
+    ));
     Ok(())
 }
 
-async fn create_requirements_file(setup_type: &str) -> Result<()> {
-    let spinner = ProgressBar::new_spinner();
-    spinner.set_style(spinner_style());
-    spinner.set_message("Writing requirements.txt...");
-    spinner.enable_steady_tick(Duration::from_millis(100));
-
-    let content = match setup_type {
-        "basic" => BASIC_TEMPLATE,
-        "advanced" => ADVANCED_TEMPLATE,
-        "data-science" => DATASCIENCE_TEMPLATE,
-        "blank" => "",
-        _ => {
-            spinner.finish_and_clear();
-            anyhow::bail!("Invalid setup type. Use 'basic', 'advanced', 'data-science', or 'blank'")
+/// `requires-python` specifier for a version such as `3.12.4`, which becomes `>=3.12`
+fn requires_python(version: &str) -> Option<String> {
+    let parts: Vec<&str> = version.split('.').collect();
+    let numeric = parts
+        .iter()
+        .all(|part| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit()));
+    if !numeric || parts.len() > 3 {
+        return None;
+    }
+    Some(format!(">={}", parts[..parts.len().min(2)].join(".")))
+}
+
+/// Sets a string key in the `[project]` table, keeping the rest of the file as is.
+///
+/// An existing value is only replaced if `overwrite` is set. Returns `None` if
+/// the file has no `[project]` table.
+fn set_project_key(pyproject: &str, name: &str, value: &str, overwrite: bool) -> Option<String> {
+    let line = format!("{} = \"{}\"", name, value);
+    let mut lines: Vec<String> = pyproject.lines().map(str::to_string).collect();
+    let start = lines.iter().position(|l| l.trim() == "[project]")?;
+    let end = lines[start + 1..]
+        .iter()
+        .position(|l| l.trim_start().starts_with('['))
+        .map_or(lines.len(), |offset| start + 1 + offset);
+
+    let key = |l: &String, name: &str| l.split_once('=').is_some_and(|(k, _)| k.trim() == name);
+    if let Some(existing) = (start + 1..end).find(|&i| key(&lines[i], name)) {
+        if !overwrite {
+            return Some(pyproject.to_string());
         }
-    };
+        lines[existing] = line;
+    } else {
+        // Keep it next to the version, where uv init puts requires-python
+        let after = (start + 1..end)
+            .find(|&i| key(&lines[i], "version"))
+            .unwrap_or(start);
+        lines.insert(after + 1, line);
+    }
+    Some(lines.join("\n") + "\n")
+}
 
-    fs::write("requirements.txt", content).await?;
-    spinner.finish_and_clear();
-    println!("{}", "✅ requirements.txt created".green());
+/// Appends `--python <version>` to a uv command when a version was requested
+fn with_python<'a>(args: &[&'a str], python: Option<&'a str>) -> Vec<&'a str> {
+    let mut args = args.to_vec();
+    if let Some(version) = python {
+        args.extend(["--python", version]);
+    }
+    args
+}
 
-    if setup_type != "blank" {
-        // Print requirements in yellow before installing
-        let reqs = fs::read_to_string("requirements.txt")
-            .await
-            .unwrap_or_default();
-        println!(
-            "{}\n{}",
-            "Installing the following requirements:".yellow(),
-            reqs.yellow()
-        );
+async fn create_requirements_file(ctx: &Ctx, settings: &ProjectSettings) -> Result<()> {
+    let step = ctx.step("write_requirements", "Writing requirements.txt...");
+    let setup_type = settings.setup;
+    let content = &setup_type.load_requirements(&settings.template_dirs)?;
 
-        let spinner = ProgressBar::new_spinner();
-        spinner.set_style(spinner_style());
-        spinner.set_message("Installing requirements...");
-        spinner.enable_steady_tick(Duration::from_millis(100));
-        run("uv", &["add", "-r", "requirements.txt"]).await?;
-        run("uv", &["sync"]).await?;
-        spinner.finish_and_clear();
-        println!("{}", "✅ requirements installed".green());
+    match fs::read_to_string(ctx.path("requirements.txt")).await {
+        Ok(existing) => {
+            ctx.write("requirements.txt", merge_lines(&existing, content))
+                .await?;
+            step.finish("requirements.txt merged with template");
+        }
+        Err(_) => {
+            ctx.write("requirements.txt", content).await?;
+            step.finish("requirements.txt created");
+        }
     }
+    Ok(())
+}
 
+async fn install_requirements(ctx: &Ctx, settings: &ProjectSettings) -> Result<()> {
+    if settings.setup == SetupType::Blank {
+        ctx.step("install_requirements", "Installing requirements...")
+            .skip("blank setup, no requirements to install");
+        return Ok(());
+    }
+
+    // A dry run never wrote the file, so show what it would have contained
+    let reqs = match fs::read_to_string(ctx.path("requirements.txt")).await {
+        Ok(reqs) => reqs,
+        Err(_) => settings.setup.load_requirements(&settings.template_dirs)?,
+    };
+    // Print requirements in yellow before installing
+    ctx.out
+        .info(format!("Installing the following requirements:\n{}", reqs));
+
+    let step = ctx.step("install_requirements", "Installing requirements...");
+    ctx.uv_command(&["add", "-r", "requirements.txt"]).await?;
+    ctx.uv_command(&["sync"]).await?;
+    step.finish("requirements installed");
     Ok(())
 }
 
-async fn download_and_write_file(url: &str, filename: &str) -> Result<()> {
-    let spinner = ProgressBar::new_spinner();
-    spinner.set_style(spinner_style());
-    spinner.set_message(format!("Downloading {}...", filename));
-    spinner.enable_steady_tick(Duration::from_millis(100));
+/// Downloads a text file and keeps a copy in the cache; offline runs read that copy instead.
+///
+/// `file://` URLs and plain paths, such as a mirror on a network share, are
+/// read directly and never cached.
+async fn download_file(ctx: &Ctx, url: &str) -> Result<String> {
+    if let Some(path) = source::local_path(url) {
+        if ctx.dry_run {
+            ctx.plan(format!("read {}", path.display()));
+            return Ok(String::new());
+        }
+        return fs::read_to_string(&path)
+            .await
+            .with_context(|| format!("Failed to read {}", path.display()));
+    }
+    if ctx.dry_run {
+        if ctx.offline {
+            ctx.plan(format!("read the cached copy of {}", url));
+        } else {
+            ctx.plan(format!("GET {}", url));
+        }
+        return Ok(String::new());
+    }
+    if ctx.offline {
+        return cache::load(url)
+            .context("offline and not cached, run `pycargo prefetch` while online to cache it");
+    }
 
-    let response = reqwest::get(url).await.context("Failed to download file")?;
+    let response = ctx
+        .send(http::client().get(url))
+        .await
+        .with_context(|| format!("Failed to download {}", url))?;
     if !response.status().is_success() {
-        spinner.finish_and_clear();
-        anyhow::bail!("HTTP error: {}", response.status());
+        anyhow::bail!(
+            "Failed to download {}: HTTP error {}",
+            url,
+            response.status()
+        );
+    }
+    let body = http::text(response).await?;
+    // A missing cache only matters for later offline runs
+    if let Err(err) = cache::store(url, &body) {
+        ctx.out.warn(format!("{:#}", err));
+    }
+    Ok(body)
+}
+
+/// Downloads a file, or returns the copy built into pycargo with a warning if that fails.
+///
+/// The text is checked against `sha256` when one is pinned, whichever copy was
+/// used, and a mismatch is an error. The flag tells whether the built-in copy was used.
+async fn download_or_embedded(
+    ctx: &Ctx,
+    url: &str,
+    embedded: &str,
+    sha256: Option<&str>,
+) -> Result<(String, bool)> {
+    let (body, built_in) = match download_file(ctx, url).await {
+        Ok(body) => (body, false),
+        Err(err) => {
+            ctx.out.warn(format!(
+                "Could not download {} ({}). Using the copy built into pycargo instead",
+                url,
+                err.root_cause()
+            ));
+            (embedded.to_string(), true)
+        }
+    };
+    if let Some(expected) = sha256 {
+        let what = if built_in {
+            format!("the built-in copy of {}", url)
+        } else {
+            url.to_string()
+        };
+        verify_sha256(ctx, &what, &body, expected)?;
     }
-    let body = response
-        .text()
+    Ok((body, built_in))
+}
+
+/// Fails unless `text` has the pinned SHA-256 digest
+fn verify_sha256(ctx: &Ctx, what: &str, text: &str, expected: &str) -> Result<()> {
+    if ctx.dry_run {
+        ctx.plan(format!(
+            "check that the SHA-256 of {} is {}",
+            what, expected
+        ));
+        return Ok(());
+    }
+    let actual: String = Sha256::digest(text.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    if !actual.eq_ignore_ascii_case(expected) {
+        anyhow::bail!(
+            "SHA-256 mismatch for {}: expected {}, got {}",
+            what,
+            expected,
+            actual
+        );
+    }
+    Ok(())
+}
+
+/// Builds `.gitignore` from the selected sections plus the setup's own patterns.
+///
+/// The sections are downloaded concurrently and an existing `.gitignore` is
+/// only extended with patterns it does not contain yet.
+async fn write_gitignore(ctx: &Ctx, settings: &ProjectSettings) -> Result<()> {
+    let step = ctx.step("download_gitignore", "Downloading .gitignore...");
+    let downloads = settings.gitignore.iter().map(|section| async move {
+        let name = section.to_string();
+        let location = settings.gitignore_location(*section);
+        let (text, _) =
+            download_or_embedded(ctx, &location, section.text(), settings.sha256(&name)).await?;
+        anyhow::Ok((name, text))
+    });
+    let mut sections = futures_util::future::join_all(downloads)
         .await
-        .context("Failed to read response body")?;
-    fs::write(filename, body)
+        .into_iter()
+        .collect::<Result<Vec<_>>>()?;
+    let entries = settings.setup.gitignore_entries();
+    if !entries.is_empty() {
+        sections.push((format!("{} setup", settings.setup), entries.join("\n")));
+    }
+    if ctx.dry_run {
+        let names: Vec<&str> = sections.iter().map(|(name, _)| name.as_str()).collect();
+        ctx.plan(format!(
+            "add the missing patterns of {} to .gitignore",
+            names.join(", ")
+        ));
+        step.finish(".gitignore planned");
+        return Ok(());
+    }
+
+    let existing = fs::read_to_string(ctx.path(".gitignore"))
         .await
-        .context("Failed to write file")?;
-    spinner.finish_and_clear();
-    println!("{}", format!("✅ Downloaded {}", filename).green());
+        .unwrap_or_default();
+    let (updated, added) = gitignore::compose(&existing, &sections);
+    if added.is_empty() {
+        step.finish(".gitignore already has every pattern");
+        return Ok(());
+    }
+    ctx.write(".gitignore", updated).await?;
+    if existing.is_empty() {
+        step.finish(format!(".gitignore created from {}", added.join(", ")));
+    } else {
+        step.finish(format!("Added {} to .gitignore", added.join(", ")));
+    }
     Ok(())
 }
 
-async fn initialize_git_repo() -> Result<()> {
-    let spinner = ProgressBar::new_spinner();
-    spinner.set_style(spinner_style());
-    spinner.set_message("Initializing Git repository...");
-    spinner.enable_steady_tick(Duration::from_millis(100));
+/// Writes the selected license with the copyright filled in and records it in pyproject.toml.
+///
+/// Licenses with an official source are downloaded like any other file; the
+/// rest only exist as built-in text.
+async fn write_license(ctx: &Ctx, settings: &ProjectSettings) -> Result<()> {
+    let license = settings.license()?;
+    let step = ctx.step(
+        "download_license",
+        format!("Writing {} LICENSE...", license.id),
+    );
+    if ctx.exists("LICENSE").await {
+        step.skip("LICENSE already exists, leaving it unchanged");
+        return Ok(());
+    }
 
-    git_command(&["init"]).await?;
-    git_command(&["config", "core.autocrlf", "true"]).await?;
-    git_command(&["add", "."]).await?;
-    git_command(&["commit", "-m", "Initial commit"]).await?;
+    let sha256 = settings.sha256(license.id);
+    let location = settings.license_location()?;
+    let (text, built_in) = match &location {
+        Some(location) => download_or_embedded(ctx, location, license.text, sha256).await?,
+        None => {
+            if let Some(expected) = sha256 {
+                let what = format!("the built-in {} text", license.id);
+                verify_sha256(ctx, &what, license.text, expected)?;
+            }
+            (license.text.to_string(), true)
+        }
+    };
+    let holder = match git_config_value(&ctx.log, "user.name").await? {
+        Some(name) => name,
+        None if ctx.dry_run => "<git user.name>".to_string(),
+        None => {
+            anyhow::bail!("Git user.name is not configured, it is needed for the copyright line")
+        }
+    };
+    let year = log::current_year();
+    let rendered = license::render(&text, &holder, year);
+    // The GPL has no copyright line of its own to fill in
+    let copyright = if rendered == text {
+        String::new()
+    } else {
+        format!(", Copyright (c) {} {}", year, holder)
+    };
+    ctx.write("LICENSE", rendered).await?;
+
+    if ctx.dry_run {
+        ctx.plan(format!(
+            "set license = \"{}\" in pyproject.toml unless it is already set",
+            license.expression
+        ));
+    } else if let Ok(pyproject) = fs::read_to_string(ctx.path("pyproject.toml")).await {
+        match set_project_key(&pyproject, "license", license.expression, false) {
+            Some(updated) if updated != pyproject => ctx.write("pyproject.toml", updated).await?,
+            Some(_) => {
+                let same = format!("license = \"{}\"", license.expression);
+                if !pyproject.lines().any(|l| l.trim() == same) {
+                    ctx.out.warn(
+                        "pyproject.toml already declares a different license, it was left unchanged",
+                    );
+                }
+            }
+            None => ctx
+                .out
+                .warn("pyproject.toml has no [project] table, the license was not recorded"),
+        }
+    }
 
-    spinner.finish_and_clear();
-    println!("{}", "✅ Git repository initialized and committed".green());
+    let source = if built_in && location.is_some() {
+        " from the built-in copy"
+    } else {
+        ""
+    };
+    step.finish(format!(
+        "{} LICENSE written{}{}",
+        license.id, source, copyright
+    ));
     Ok(())
 }
 
-async fn setup_github_remote(repo_name: &str) -> Result<String> {
-    let spinner = ProgressBar::new_spinner();
-    spinner.set_style(spinner_style());
-    spinner.set_message("Setting up GitHub remote...");
-    spinner.enable_steady_tick(Duration::from_millis(100));
+/// Appends the non-empty, non-comment lines of `additions` that `existing` does not already contain
+fn merge_lines(existing: &str, additions: &str) -> String {
+    let present: std::collections::HashSet<&str> = existing.lines().map(str::trim).collect();
+    let missing: Vec<&str> = additions
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#') && !present.contains(line))
+        .collect();
+
+    let mut merged = existing.to_string();
+    if missing.is_empty() {
+        return merged;
+    }
+    if !merged.is_empty() && !merged.ends_with('\n') {
+        merged.push('\n');
+    }
+    for line in missing {
+        merged.push_str(line);
+        merged.push('\n');
+    }
+    merged
+}
+
+/// Files pycargo writes or merges into, which are all it commits in an existing repository
+const BOOTSTRAP_FILES: [&str; 7] = [
+    ".gitignore",
+    "LICENSE",
+    "README.md",
+    "requirements.txt",
+    "pyproject.toml",
+    ".python-version",
+    "uv.lock",
+];
+
+async fn initialize_git_repo(ctx: &Ctx, branch: &str) -> Result<()> {
+    let step = ctx.step("git_init", "Initializing Git repository...");
+
+    let existing_repo = ctx.exists(".git").await;
+    if !existing_repo {
+        ctx.git_command(&["init", "-b", branch]).await?;
+        ctx.git_command(&["config", "core.autocrlf", "true"])
+            .await?;
+    }
 
-    git_command(&["branch", "-M", "main"]).await?;
-    let username = get_git_username().await?;
-    let remote_url = format!("https://github.com/{}/{}.git", username, repo_name);
-    git_command(&["remote", "add", "origin", &remote_url]).await?;
-    git_command(&["push", "-u", "origin", "main"]).await?;
+    // In an existing repository only pycargo's files are committed, never unrelated work
+    let paths = if existing_repo {
+        bootstrap_paths(ctx).await?
+    } else {
+        vec!["."]
+    };
+    if paths.is_empty() {
+        step.skip("Git repository already up to date");
+        return Ok(());
+    }
+    let mut add = vec!["add", "--"];
+    add.extend(&paths);
+    ctx.git_command(&add).await?;
+
+    if !ctx.dry_run && !git_has_staged_changes(ctx, &paths).await? {
+        step.skip("Git repository already up to date");
+        return Ok(());
+    }
 
-    spinner.finish_and_clear();
-    println!("{}", "✅ GitHub remote configured".green());
+    let message = if existing_repo {
+        "Bootstrap project with pycargo"
+    } else {
+        "Initial commit"
+    };
+    let mut commit = vec!["commit", "-m", message, "--"];
+    commit.extend(&paths);
+    ctx.git_command(&commit).await?;
+
+    if existing_repo {
+        step.finish("Existing Git repository committed");
+    } else {
+        step.finish("Git repository initialized and committed");
+    }
+    Ok(())
+}
+
+/// Returns whether the index contains changes to `paths` that have not been committed yet
+async fn git_has_staged_changes(ctx: &Ctx, paths: &[&str]) -> Result<bool> {
+    let mut args = vec!["diff", "--cached", "--quiet", "--"];
+    args.extend(paths);
+    let output = ctx
+        .query("git", &args)
+        .await
+        .context("Failed to check staged changes")?;
+    Ok(!output.status.success())
+}
+
+/// The files pycargo writes or merges into, as far as they exist and Git does not ignore them
+async fn bootstrap_paths(ctx: &Ctx) -> Result<Vec<&'static str>> {
+    let mut paths = Vec::new();
+    for file in BOOTSTRAP_FILES {
+        // In a dry run the files have not been written yet
+        if ctx.dry_run || (ctx.exists(file).await && !git_ignores(ctx, file).await?) {
+            paths.push(file);
+        }
+    }
+    Ok(paths)
+}
+
+/// Returns whether `file` is untracked and ignored, which makes `git add` refuse it
+async fn git_ignores(ctx: &Ctx, file: &str) -> Result<bool> {
+    let output = ctx
+        .query("git", &["check-ignore", "--quiet", "--", file])
+        .await
+        .context("Failed to check ignored files")?;
+    Ok(output.status.success())
+}
+
+async fn setup_github_remote(ctx: &Ctx, repo_name: &str, branch: &str) -> Result<String> {
+    let step = ctx.step("github_remote", "Setting up GitHub remote...");
+
+    ctx.git_command(&["branch", "-M", branch]).await?;
+    let username = get_git_username(ctx).await?;
+    let remote_url = format!("https://github.com/{}/{}.git", username, repo_name);
+    // A resumed setup may already have added the remote before the push failed
+    let action = if git_has_remote(ctx, "origin").await? {
+        "set-url"
+    } else {
+        "add"
+    };
+    ctx.git_command(&["remote", action, "origin", &remote_url])
+        .await?;
+    ctx.git_command(&["push", "-u", "origin", branch]).await?;
+
+    step.finish(format!(
+        "GitHub repository created: {}",
+        remote_url.trim_end_matches(".git")
+    ));
     Ok(remote_url)
 }
 
-async fn create_github_repo(name: &str, private: bool) -> Result<()> {
-    let spinner = ProgressBar::new_spinner();
-    spinner.set_style(spinner_style());
-    spinner.set_message("Creating GitHub repository via API...");
-    spinner.enable_steady_tick(Duration::from_millis(100));
+async fn git_has_remote(ctx: &Ctx, name: &str) -> Result<bool> {
+    if ctx.dry_run {
+        return Ok(false);
+    }
+    let output = ctx
+        .query("git", &["remote", "get-url", name])
+        .await
+        .context("Failed to check git remotes")?;
+    Ok(output.status.success())
+}
 
-    let token = env::var("GITHUB_TOKEN").context("GITHUB_TOKEN not set")?;
-    let client = reqwest::Client::new();
+/// Creates the repository and returns its `owner/name`
+async fn create_github_repo(ctx: &Ctx, name: &str, private: bool) -> Result<Option<String>> {
+    if ctx.dry_run {
+        ctx.plan(format!(
+            "POST https://api.github.com/user/repos {}",
+            serde_json::json!({ "name": name, "private": private })
+        ));
+        return Ok(None);
+    }
+
+    let step = ctx.step("github_create", "Creating GitHub repository via API...");
 
-    let response = client
+    let token = env::var("GITHUB_TOKEN").context("GITHUB_TOKEN not set")?;
+    let request = http::client()
         .post("https://api.github.com/user/repos")
         .bearer_auth(token)
-        .header("User-Agent", "pycargo")
-        .json(&serde_json::json!({ "name": name, "private": private }))
-        .send()
+        .json(&serde_json::json!({ "name": name, "private": private }));
+
+    let response = ctx
+        .send(request)
         .await
         .context("Failed to create GitHub repository")?;
 
     if !response.status().is_success() {
-        spinner.finish_and_clear();
-        let error_body = response.text().await.unwrap_or_default();
+        let error_body = http::text(response).await.unwrap_or_default();
         anyhow::bail!("GitHub API error: {}", error_body);
     }
 
-    spinner.finish_and_clear();
-    println!(
-        "{}",
-        format!("✅ GitHub repository '{}' created", name).green()
-    );
-    Ok(())
-}
-
-async fn run(cmd: &str, args: &[&str]) -> Result<()> {
-    let output = Command::new(cmd)
-        .args(args)
-        .output()
-        .await
-        .with_context(|| format!("Failed to execute: {} {}", cmd, args.join(" ")))?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        anyhow::bail!(
-            "{}",
-            format!(
-                "Command failed: {} {}\nError Output: {}",
-                cmd,
-                args.join(" "),
-                stderr
-            )
-            .red()
-        );
+    // Remember the repository so it can be deleted if a later step fails
+    let repo: serde_json::Value = serde_json::from_str(&http::text(response).await?)
+        .context("Failed to parse GitHub API response")?;
+    let full_name = repo["full_name"].as_str().map(str::to_string);
+    if let Some(full_name) = &full_name {
+        ctx.record_github_repo(full_name);
     }
 
-    Ok(())
+    step.finish(format!("GitHub repository '{}' created", name));
+    Ok(full_name)
 }
 
-async fn git_command(args: &[&str]) -> Result<()> {
-    run("git", args).await
-}
+/// Deletes a repository by its `owner/name`; requires the `delete_repo` token scope
+async fn delete_github_repo(ctx: &Ctx, full_name: &str) -> Result<()> {
+    let token = env::var("GITHUB_TOKEN").context("GITHUB_TOKEN not set")?;
+    let request = http::client()
+        .delete(format!("https://api.github.com/repos/{}", full_name))
+        .bearer_auth(token);
+    let response = ctx
+        .send(request)
+        .await
+        .context("Failed to delete GitHub repository")?;
 
-fn validate_env_vars() -> Result<()> {
-    if env::var("GITHUB_TOKEN").is_err() {
-        anyhow::bail!("{}", "GITHUB_TOKEN environment variable is not set".red());
+    if !response.status().is_success() {
+        let error_body = http::text(response).await.unwrap_or_default();
+        anyhow::bail!("GitHub API error: {}", error_body);
     }
     Ok(())
 }
 
-fn get_user_input() -> String {
-    let mut input = String::new();
-    io::stdin()
-        .read_line(&mut input)
-        .expect("Failed to read input");
-    input.trim().to_string()
-}
-
 /// Retrieves the GitHub username from global git config
-async fn get_git_username() -> Result<String> {
-    let output = Command::new("git")
-        .args(["config", "--global", "user.name"])
-        .output()
-        .await
-        .context("Failed to retrieve GitHub username from git config")?;
+async fn get_git_username(ctx: &Ctx) -> Result<String> {
+    if ctx.dry_run {
+        return Ok("<git user.name>".to_string());
+    }
+
+    let output = ctx::query(
+        &ctx.log,
+        Path::new("."),
+        "git",
+        &["config", "--global", "user.name"],
+    )
+    .await
+    .context("Failed to retrieve GitHub username from git config")?;
 
     let username = String::from_utf8(output.stdout)
         .context("Failed to parse GitHub username from git config output")?
@@ -370,38 +1341,124 @@ async fn get_git_username() -> Result<String> {
     Ok(username)
 }
 
-/// Checks and sets git global configuration if missing, with spinner feedback
-async fn check_git_config(key: &str, prompt: &str) -> Result<()> {
-    let spinner = ProgressBar::new_spinner();
-    spinner.set_style(spinner_style());
-    spinner.set_message(format!("Checking git config for {}...", key));
-    spinner.enable_steady_tick(Duration::from_millis(100));
+/// Checks and sets git global configuration if missing, with spinner feedback.
+///
+/// A value passed on the command line replaces the prompt; in non-interactive
+/// mode a missing value without one is an error.
+async fn check_git_config(
+    ctx: &Ctx,
+    key: &str,
+    prompt: &str,
+    identity: &GitIdentityArgs,
+) -> Result<()> {
+    let provided = identity.value_for(key);
+    if ctx.dry_run {
+        let fallback = match provided {
+            Some(value) => format!("running `git config --global {} {}`", key, value),
+            None if ctx.non_interactive => "failing".to_string(),
+            None => format!(
+                "prompting for your {} and running `git config --global {} <value>`",
+                prompt, key
+            ),
+        };
+        ctx.plan(format!(
+            "run `git config --get {}`, {} if it is missing",
+            key, fallback
+        ));
+        return Ok(());
+    }
 
-    let output = Command::new("git")
-        .args(["config", "--get", key])
-        .output()
-        .await
-        .context("Failed to get git config")?;
+    let step = ctx.step(
+        format!("git_{}", key.replace('.', "_")),
+        format!("Checking git config for {}...", key),
+    );
 
-    if output.stdout.is_empty() {
-        spinner.finish_and_clear();
-        println!(
-            "Git {} is not configured. Please enter your {}:",
-            key, prompt
-        );
-        let input = get_user_input();
-
-        let spinner2 = ProgressBar::new_spinner();
-        spinner2.set_style(spinner_style());
-        spinner2.set_message(format!("Setting git {}...", key));
-        spinner2.enable_steady_tick(Duration::from_millis(100));
-        git_command(&["config", "--global", key, &input]).await?;
-        spinner2.finish_and_clear();
-        println!("{}", format!("✅ Git {} configured", key).green());
+    if git_config_value(&ctx.log, key).await?.is_none() {
+        let input = match provided {
+            Some(value) => value.to_string(),
+            None if ctx.non_interactive => anyhow::bail!(
+                "{}",
+                ctx.out.failure(format!(
+                    "Git {} is not configured. Pass --git-{} or run `git config --global {} <value>`",
+                    key, prompt, key
+                ))
+            ),
+            None => {
+                step.pause();
+                let input = ctx
+                    .ask(format!(
+                        "Git {} is not configured. Please enter your {}:",
+                        key, prompt
+                    ))
+                    .await?;
+                if input.is_empty() {
+                    anyhow::bail!("{}", ctx.out.failure(format!("Git {} cannot be empty", key)));
+                }
+                input
+            }
+        };
+
+        ctx.run_global("git", &["config", "--global", key, &input])
+            .await?;
+        step.finish(format!("Git {} configured", key));
     } else {
-        spinner.finish_and_clear();
-        println!("{}", format!("✅ Git {} already configured", key).green());
+        step.finish(format!("Git {} already configured", key));
     }
 
     Ok(())
 }
+
+/// Reads a git config value, returning `None` if it is unset or empty
+async fn git_config_value(log: &RunLog, key: &str) -> Result<Option<String>> {
+    let output = ctx::query(log, Path::new("."), "git", &["config", "--get", key])
+        .await
+        .context("Failed to get git config")?;
+
+    let value = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    Ok((!value.is_empty()).then_some(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PYPROJECT: &str = "[project]\nname = \"demo\"\nversion = \"0.1.0\"\nrequires-python = \">=3.9\"\n\n[tool.uv]\nlicense = \"x\"\n";
+
+    #[test]
+    fn merge_lines_appends_only_missing_entries() {
+        let merged = merge_lines("numpy\npandas", "# Data\npandas\n\nscipy\n  numpy  \n");
+        assert_eq!(merged, "numpy\npandas\nscipy\n");
+        assert_eq!(merge_lines(&merged, "scipy\n"), merged);
+        assert_eq!(merge_lines("", "numpy\n"), "numpy\n");
+    }
+
+    #[test]
+    fn requires_python_keeps_major_and_minor() {
+        assert_eq!(requires_python("3.12.4").as_deref(), Some(">=3.12"));
+        assert_eq!(requires_python("3.11").as_deref(), Some(">=3.11"));
+        assert_eq!(requires_python("3").as_deref(), Some(">=3"));
+        assert_eq!(requires_python("pypy@3.10"), None);
+        assert_eq!(requires_python("3..1"), None);
+        assert_eq!(requires_python("3.12.4.1"), None);
+    }
+
+    #[test]
+    fn set_project_key_replaces_only_when_asked() {
+        let kept = set_project_key(PYPROJECT, "requires-python", ">=3.12", false).unwrap();
+        assert_eq!(kept, PYPROJECT);
+        let replaced = set_project_key(PYPROJECT, "requires-python", ">=3.12", true).unwrap();
+        assert_eq!(replaced, PYPROJECT.replace(">=3.9", ">=3.12"));
+    }
+
+    #[test]
+    fn set_project_key_adds_after_version_in_project_table() {
+        let updated = set_project_key(PYPROJECT, "license", "MIT", false).unwrap();
+        assert!(updated.contains("version = \"0.1.0\"\nlicense = \"MIT\"\nrequires-python"));
+        // The key of the same name in another table is not the project's
+        assert!(updated.ends_with("[tool.uv]\nlicense = \"x\"\n"));
+        assert_eq!(
+            set_project_key("[tool.uv]\n", "license", "MIT", false),
+            None
+        );
+    }
+}
@@ -0,0 +1,332 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use colored::*;
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::env;
+use std::fmt::Display;
+use std::io::{IsTerminal, Write};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// How progress and results are reported
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Colors, emoji and spinners for an interactive terminal
+    #[value(alias = "human")]
+    Fancy,
+    /// Uncolored text without emoji or animation, one line per message
+    Plain,
+    /// Only warnings, prompts and errors
+    Quiet,
+    /// One JSON event per line on stdout for tools wrapping pycargo
+    Json,
+}
+
+impl OutputFormat {
+    /// Fancy output on an interactive terminal, plain output everywhere else
+    fn detect() -> Self {
+        let dumb_terminal = env::var("TERM").is_ok_and(|term| term == "dumb");
+        if std::io::stdout().is_terminal() && !dumb_terminal {
+            OutputFormat::Fancy
+        } else {
+            OutputFormat::Plain
+        }
+    }
+}
+
+/// Everything shown to the user goes through this type.
+///
+/// Messages are passed without decoration; colors, emoji and spinners are
+/// added here only in fancy mode. In JSON mode progress messages are dropped,
+/// and query results and warnings are written as `data` and `warning` events,
+/// so stdout stays machine-readable.
+#[derive(Clone)]
+pub struct Output {
+    format: OutputFormat,
+    verbose: bool,
+    progress: MultiProgress,
+}
+
+impl Output {
+    /// Creates the output layer, detecting the mode when none was requested
+    pub fn new(format: Option<OutputFormat>, verbose: bool) -> Self {
+        let format = format.unwrap_or_else(OutputFormat::detect);
+        let no_color = env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+        if format != OutputFormat::Fancy || no_color {
+            colored::control::set_override(false);
+        }
+        Self {
+            format,
+            verbose,
+            progress: MultiProgress::new(),
+        }
+    }
+
+    pub fn is_json(&self) -> bool {
+        self.format == OutputFormat::Json
+    }
+
+    fn is_fancy(&self) -> bool {
+        self.format == OutputFormat::Fancy
+    }
+
+    /// Whether regular progress messages are shown
+    fn is_verbose(&self) -> bool {
+        matches!(self.format, OutputFormat::Fancy | OutputFormat::Plain)
+    }
+
+    /// Prepends an emoji in fancy mode only
+    fn decorate(&self, icon: &str, message: impl Display) -> String {
+        if self.is_fancy() {
+            format!("{} {}", icon, message)
+        } else {
+            message.to_string()
+        }
+    }
+
+    fn write_line(&self, text: impl Display) {
+        self.progress.suspend(|| println!("{}", text));
+    }
+
+    /// Prints a line of progress output without tearing active spinners
+    pub fn print(&self, text: impl Display) {
+        if self.is_verbose() {
+            self.write_line(text);
+        }
+    }
+
+    /// Prints the result of a query command such as `config get`; shown in every mode
+    pub fn data(&self, text: impl Display) {
+        if self.is_json() {
+            self.event("data", serde_json::json!({ "text": text.to_string() }));
+        } else {
+            self.write_line(text);
+        }
+    }
+
+    /// Prints a section header such as `=== 📦 File Downloads ===`
+    pub fn header(&self, icon: &str, title: &str) {
+        let title = format!("\n=== {} ===", self.decorate(icon, title));
+        self.print(title.bold().blue());
+    }
+
+    /// Prints a standalone, emphasized line such as the completion message
+    pub fn highlight(&self, icon: &str, message: impl Display, color: Color) {
+        self.print(self.decorate(icon, message).color(color).bold());
+    }
+
+    pub fn info(&self, message: impl Display) {
+        self.print(message.to_string().yellow());
+    }
+
+    pub fn success(&self, message: impl Display) {
+        self.print(self.decorate("✅", message).green());
+    }
+
+    /// Prints a warning; shown in quiet mode too
+    pub fn warn(&self, message: impl Display) {
+        if self.is_json() {
+            self.event(
+                "warning",
+                serde_json::json!({ "message": message.to_string() }),
+            );
+            return;
+        }
+        let message = if self.is_fancy() {
+            format!("⚠️ {}", message)
+        } else {
+            format!("warning: {}", message)
+        };
+        self.write_line(message.yellow());
+    }
+
+    /// Formats an error message for `anyhow::bail!`
+    pub fn failure(&self, message: impl Display) -> String {
+        self.decorate("❌", message).red().to_string()
+    }
+
+    /// Prints an error to stderr, for failures that do not end in a returned error
+    pub fn error(&self, message: impl Display) {
+        eprintln!("{}", self.failure(message));
+    }
+
+    /// Reports an action that a dry run would perform
+    pub fn plan(&self, action: impl Display) {
+        if self.is_json() {
+            self.event("plan", serde_json::json!({ "action": action.to_string() }));
+        } else {
+            self.write_line(format!("{} {}", "[dry-run]".cyan().bold(), action));
+        }
+    }
+
+    /// Shows a question to the user; kept off stdout unless progress is shown there
+    pub fn prompt(&self, question: impl Display) {
+        if self.is_verbose() {
+            self.write_line(question);
+        } else {
+            eprintln!("{}", question);
+        }
+    }
+
+    /// Clears the spinners and stops drawing them, so a prompt is not overdrawn by other steps
+    pub fn hide_progress(&self) {
+        // Nothing to clear if the display was never drawn
+        let _ = self.progress.clear();
+        self.progress.set_draw_target(ProgressDrawTarget::hidden());
+    }
+
+    /// Draws the spinners again after `hide_progress`
+    pub fn show_progress(&self) {
+        if self.is_fancy() {
+            self.progress.set_draw_target(ProgressDrawTarget::stderr());
+        }
+    }
+
+    /// Writes a JSON event line; ignored in the other modes
+    pub fn event(&self, kind: &str, fields: serde_json::Value) {
+        if !self.is_json() {
+            return;
+        }
+        let mut event = serde_json::json!({
+            "event": kind,
+            "timestamp_ms": SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or_default(),
+        });
+        if let (Some(event), serde_json::Value::Object(fields)) = (event.as_object_mut(), fields) {
+            event.extend(fields);
+        }
+        // A consumer that stopped reading is not a reason to abort the setup
+        let _ = writeln!(std::io::stdout().lock(), "{}", event);
+    }
+
+    /// Starts a spinner; plain mode prints the message once instead of animating it
+    pub fn spinner(&self, message: impl Into<String>) -> ProgressBar {
+        let message = message.into();
+        match self.format {
+            OutputFormat::Fancy => {
+                let spinner = self.progress.add(ProgressBar::new_spinner());
+                spinner.set_style(spinner_style());
+                spinner.set_message(message);
+                spinner.enable_steady_tick(Duration::from_millis(100));
+                spinner
+            }
+            OutputFormat::Plain => {
+                self.write_line(message);
+                ProgressBar::hidden()
+            }
+            OutputFormat::Quiet | OutputFormat::Json => ProgressBar::hidden(),
+        }
+    }
+
+    /// Starts the overall "step 4/9" counter with the elapsed time, shown above the spinners
+    pub fn overall(&self, total: usize) -> ProgressBar {
+        if !self.is_fancy() {
+            return ProgressBar::hidden();
+        }
+        let bar = self.progress.insert(0, ProgressBar::new(total as u64));
+        bar.set_style(
+            ProgressStyle::with_template("{bar:30.cyan/blue} step {pos}/{len} [{elapsed_precise}]")
+                .expect("Failed to set progress template")
+                .progress_chars("=> "),
+        );
+        bar.enable_steady_tick(Duration::from_secs(1));
+        bar
+    }
+
+    /// Starts a view of a running command's output.
+    ///
+    /// Fancy mode shows the latest line under the spinners, or the last few
+    /// lines with `--verbose`. Plain mode prints every line with `--verbose`.
+    pub fn live(&self) -> LiveOutput {
+        let view = match self.format {
+            OutputFormat::Fancy => {
+                let bar = self.progress.add(ProgressBar::new_spinner());
+                bar.set_style(ProgressStyle::with_template("{msg}").expect("valid template"));
+                let limit = if self.verbose { VERBOSE_TAIL_LINES } else { 1 };
+                LiveView::Tail { bar, limit }
+            }
+            OutputFormat::Plain if self.verbose => LiveView::Echo(self.clone()),
+            _ => LiveView::Hidden,
+        };
+        LiveOutput {
+            view,
+            recent: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Emits the final summary event and passes the result through
+    pub fn finish<T: Serialize>(&self, result: Result<Option<T>>) -> Result<()> {
+        match &result {
+            Ok(Some(summary)) => self.event(
+                "summary",
+                serde_json::json!({ "status": "success", "result": summary }),
+            ),
+            Ok(None) => self.event("summary", serde_json::json!({ "status": "dry_run" })),
+            Err(err) => self.event(
+                "summary",
+                serde_json::json!({ "status": "failed", "error": format!("{:#}", err) }),
+            ),
+        }
+        result.map(|_| ())
+    }
+}
+
+/// Lines of command output kept under the spinner with `--verbose`
+const VERBOSE_TAIL_LINES: usize = 10;
+
+/// Longest line shown under a spinner, so that wrapping does not break redrawing
+const MAX_LIVE_LINE_CHARS: usize = 120;
+
+/// Output of a running command, shown as it arrives and cleared when dropped
+pub struct LiveOutput {
+    view: LiveView,
+    recent: Mutex<VecDeque<String>>,
+}
+
+enum LiveView {
+    Hidden,
+    Tail { bar: ProgressBar, limit: usize },
+    Echo(Output),
+}
+
+impl LiveOutput {
+    pub fn line(&self, line: &str) {
+        // Progress bars redraw with carriage returns; only the final state is interesting
+        let line = line.trim_end().rsplit('\r').next().unwrap_or_default();
+        if line.trim().is_empty() {
+            return;
+        }
+        match &self.view {
+            LiveView::Hidden => {}
+            LiveView::Echo(out) => out.write_line(format!("  {}", line)),
+            LiveView::Tail { bar, limit } => {
+                let mut recent = self.recent.lock().expect("live output lock poisoned");
+                recent.push_back(line.chars().take(MAX_LIVE_LINE_CHARS).collect());
+                while recent.len() > *limit {
+                    recent.pop_front();
+                }
+                let shown: Vec<String> = recent.iter().map(|l| format!("  {}", l)).collect();
+                bar.set_message(shown.join("\n").dimmed().to_string());
+            }
+        }
+    }
+}
+
+impl Drop for LiveOutput {
+    fn drop(&mut self) {
+        if let LiveView::Tail { bar, .. } = &self.view {
+            bar.finish_and_clear();
+        }
+    }
+}
+
+fn spinner_style() -> ProgressStyle {
+    ProgressStyle::default_spinner()
+        .tick_strings(&["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"])
+        .template("{spinner} {msg}")
+        .expect("Failed to set spinner template")
+}
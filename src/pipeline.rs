@@ -0,0 +1,531 @@
+use anyhow::{Context, Result};
+use colored::*;
+use futures_util::stream::{FuturesUnordered, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::cli::GitIdentityArgs;
+use crate::config::ProjectSettings;
+use crate::ctx::Ctx;
+
+/// Directory inside a project where pycargo keeps its own files
+const PROJECT_DIR: &str = ".pycargo";
+
+const STATE_FILE: &str = "state.json";
+
+/// One step of the setup, listed in the order it is reported
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Step {
+    CreateDir,
+    GitUserName,
+    GitUserEmail,
+    CheckUv,
+    InstallPython,
+    UvInit,
+    PinPython,
+    WriteReadme,
+    CreateVenv,
+    WriteRequirements,
+    InstallRequirements,
+    DownloadGitignore,
+    DownloadLicense,
+    GitInit,
+    GithubCreate,
+    GithubRemote,
+}
+
+impl Step {
+    const ALL: [Step; 16] = [
+        Step::CreateDir,
+        Step::GitUserName,
+        Step::GitUserEmail,
+        Step::CheckUv,
+        Step::InstallPython,
+        Step::UvInit,
+        Step::PinPython,
+        Step::WriteReadme,
+        Step::CreateVenv,
+        Step::WriteRequirements,
+        Step::InstallRequirements,
+        Step::DownloadGitignore,
+        Step::DownloadLicense,
+        Step::GitInit,
+        Step::GithubCreate,
+        Step::GithubRemote,
+    ];
+
+    /// Identifier used in `state.json` and in JSON step events
+    pub fn id(self) -> &'static str {
+        match self {
+            Step::CreateDir => "create_dir",
+            Step::GitUserName => "git_user_name",
+            Step::GitUserEmail => "git_user_email",
+            Step::CheckUv => "check_uv",
+            Step::InstallPython => "install_python",
+            Step::UvInit => "uv_init",
+            Step::PinPython => "pin_python",
+            Step::WriteReadme => "write_readme",
+            Step::CreateVenv => "create_venv",
+            Step::WriteRequirements => "write_requirements",
+            Step::InstallRequirements => "install_requirements",
+            Step::DownloadGitignore => "download_gitignore",
+            Step::DownloadLicense => "download_license",
+            Step::GitInit => "git_init",
+            Step::GithubCreate => "github_create",
+            Step::GithubRemote => "github_remote",
+        }
+    }
+
+    /// Steps that must have completed before this one can start.
+    ///
+    /// Steps left out of a setup (such as `CreateDir` for `init`) count as completed.
+    fn deps(self) -> &'static [Step] {
+        match self {
+            Step::CreateDir | Step::GitUserName | Step::CheckUv => &[],
+            // Prompts in a predictable order and never writes ~/.gitconfig at the same time
+            Step::GitUserEmail => &[Step::GitUserName],
+            Step::InstallPython => &[Step::CheckUv],
+            Step::UvInit => &[Step::CreateDir, Step::InstallPython],
+            Step::PinPython => &[Step::UvInit],
+            // `uv init` creates an empty README.md that is filled in afterwards
+            Step::WriteReadme => &[Step::UvInit],
+            Step::CreateVenv => &[Step::UvInit, Step::PinPython],
+            Step::WriteRequirements => &[Step::CreateDir],
+            // Records the license in pyproject.toml and the copyright holder in LICENSE.
+            // `uv add` rewrites pyproject.toml, so it must not run at the same time.
+            Step::DownloadLicense => &[
+                Step::UvInit,
+                Step::PinPython,
+                Step::InstallRequirements,
+                Step::GitUserName,
+            ],
+            Step::InstallRequirements => &[Step::CreateVenv, Step::WriteRequirements],
+            // `uv init` may write its own .gitignore, which the download is merged into
+            Step::DownloadGitignore => &[Step::UvInit],
+            Step::GitInit => &[
+                Step::GitUserName,
+                Step::GitUserEmail,
+                Step::WriteReadme,
+                Step::InstallRequirements,
+                Step::DownloadGitignore,
+                Step::DownloadLicense,
+            ],
+            Step::GithubCreate => &[Step::GitInit],
+            Step::GithubRemote => &[Step::GithubCreate],
+        }
+    }
+
+    async fn run(self, ctx: &Ctx, state: &State) -> Result<Outcome> {
+        let settings = &state.settings;
+        match self {
+            Step::CreateDir => crate::create_project_dir(ctx, &state.project_dir).await?,
+            Step::GitUserName => {
+                crate::check_git_config(ctx, "user.name", "name", &state.identity).await?
+            }
+            Step::GitUserEmail => {
+                crate::check_git_config(ctx, "user.email", "email", &state.identity).await?
+            }
+            Step::CheckUv => crate::check_uv_installation(ctx).await?,
+            Step::InstallPython => crate::install_python(ctx, python(settings)?).await?,
+            Step::UvInit => crate::uv_init(ctx, settings.python.as_deref()).await?,
+            Step::PinPython => crate::pin_python(ctx, python(settings)?).await?,
+            Step::WriteReadme => crate::write_readme(ctx, &state.project_dir).await?,
+            Step::CreateVenv => crate::create_venv(ctx, settings.python.as_deref()).await?,
+            Step::WriteRequirements => crate::create_requirements_file(ctx, settings).await?,
+            Step::InstallRequirements => crate::install_requirements(ctx, settings).await?,
+            Step::DownloadGitignore => crate::write_gitignore(ctx, settings).await?,
+            Step::DownloadLicense => crate::write_license(ctx, settings).await?,
+            Step::GitInit => crate::initialize_git_repo(ctx, &settings.branch).await?,
+            Step::GithubCreate => {
+                let github = settings
+                    .github
+                    .as_ref()
+                    .context("No GitHub repository requested")?;
+                let repo = crate::create_github_repo(ctx, &github.name, github.private).await?;
+                return Ok(Outcome::GithubRepo(repo));
+            }
+            Step::GithubRemote => {
+                let github = settings
+                    .github
+                    .as_ref()
+                    .context("No GitHub repository requested")?;
+                let remote_url =
+                    crate::setup_github_remote(ctx, &github.name, &settings.branch).await?;
+                return Ok(Outcome::RemoteUrl(remote_url));
+            }
+        }
+        Ok(Outcome::Done)
+    }
+}
+
+/// The Python version of a setup that includes the Python steps
+fn python(settings: &ProjectSettings) -> Result<&str> {
+    settings
+        .python
+        .as_deref()
+        .context("No Python version requested")
+}
+
+/// What a finished step adds to the saved state
+enum Outcome {
+    Done,
+    GithubRepo(Option<String>),
+    RemoteUrl(String),
+}
+
+/// How a step ended in this run, as listed in the timing summary
+#[derive(Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum StepStatus {
+    Done,
+    Skipped,
+    Failed,
+    /// Not started because an earlier step failed
+    NotRun,
+    /// Completed by an earlier run that this one resumed
+    Previous,
+}
+
+impl StepStatus {
+    fn label(self) -> &'static str {
+        match self {
+            StepStatus::Done => "done",
+            StepStatus::Skipped => "skipped",
+            StepStatus::Failed => "failed",
+            StepStatus::NotRun => "not run",
+            StepStatus::Previous => "previous run",
+        }
+    }
+
+    fn color(self) -> Color {
+        match self {
+            StepStatus::Done => Color::Green,
+            StepStatus::Skipped => Color::Cyan,
+            StepStatus::Failed => Color::Red,
+            StepStatus::NotRun => Color::Yellow,
+            StepStatus::Previous => Color::BrightBlack,
+        }
+    }
+}
+
+/// Which command started a setup, deciding the steps it runs
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Kind {
+    New,
+    Init,
+}
+
+/// Everything needed to continue an unfinished setup, stored in `.pycargo/state.json`
+#[derive(Clone, Serialize, Deserialize)]
+pub struct State {
+    pub kind: Kind,
+    /// Where the finished project lives
+    pub project_dir: PathBuf,
+    pub settings: ProjectSettings,
+    pub identity: GitIdentityArgs,
+    pub completed: Vec<Step>,
+    /// Steps that failed in the last run; concurrent steps can fail together
+    pub failed: Vec<Step>,
+    /// `owner/name` of the GitHub repository created by this setup
+    pub github_repo: Option<String>,
+    pub remote_url: Option<String>,
+}
+
+impl State {
+    pub fn new(
+        kind: Kind,
+        project_dir: PathBuf,
+        settings: ProjectSettings,
+        identity: GitIdentityArgs,
+    ) -> Self {
+        Self {
+            kind,
+            project_dir,
+            settings,
+            identity,
+            completed: Vec::new(),
+            failed: Vec::new(),
+            github_repo: None,
+            remote_url: None,
+        }
+    }
+
+    /// The steps this setup consists of, completed or not
+    pub fn steps(&self) -> Vec<Step> {
+        Step::ALL
+            .into_iter()
+            .filter(|step| match step {
+                Step::CreateDir => self.kind == Kind::New,
+                Step::InstallPython | Step::PinPython => self.settings.python.is_some(),
+                Step::GithubCreate | Step::GithubRemote => self.settings.github.is_some(),
+                _ => true,
+            })
+            .collect()
+    }
+
+    /// Whether the project directory exists yet; saving creates it, which must be left to `create_dir`
+    fn has_root(&self) -> bool {
+        self.kind != Kind::New || self.completed.contains(&Step::CreateDir)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.steps()
+            .iter()
+            .all(|step| self.completed.contains(step))
+    }
+
+    /// Loads the state saved in `root`, if there is one
+    pub fn load(root: &Path) -> Result<Option<Self>> {
+        let path = root.join(PROJECT_DIR).join(STATE_FILE);
+        match std::fs::read_to_string(&path) {
+            Ok(text) => serde_json::from_str(&text)
+                .map(Some)
+                .with_context(|| format!("Invalid setup state {}", path.display())),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err).with_context(|| format!("Failed to read {}", path.display())),
+        }
+    }
+
+    /// Deletes the state saved in `root`
+    pub fn discard(root: &Path) -> Result<PathBuf> {
+        let path = root.join(PROJECT_DIR).join(STATE_FILE);
+        std::fs::remove_file(&path)
+            .with_context(|| format!("Failed to remove {}", path.display()))?;
+        Ok(path)
+    }
+
+    fn save(&self, root: &Path) -> Result<()> {
+        let path = ensure_project_dir(root)?.join(STATE_FILE);
+        let text = serde_json::to_string_pretty(self).context("Failed to serialize setup state")?;
+        std::fs::write(&path, text).with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+/// Creates the project's `.pycargo/` directory, keeping it out of the project's repository
+pub fn ensure_project_dir(root: &Path) -> Result<PathBuf> {
+    let dir = root.join(PROJECT_DIR);
+    std::fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    let ignore = dir.join(".gitignore");
+    if !ignore.exists() {
+        std::fs::write(&ignore, "*\n")
+            .with_context(|| format!("Failed to write {}", ignore.display()))?;
+    }
+    Ok(dir)
+}
+
+/// Runs every step that has not completed yet, saving progress after each one.
+///
+/// A step starts as soon as the steps it depends on have completed, so
+/// independent steps such as the downloads run while `uv` is busy. After the
+/// first failure no new steps are started, but the ones already running are
+/// allowed to finish so their progress is kept.
+///
+/// The caller has already printed the Project Setup header.
+pub async fn run(ctx: &Ctx, state: &mut State) -> Result<()> {
+    let mut pending: Vec<Step> = state
+        .steps()
+        .into_iter()
+        .filter(|step| !state.completed.contains(step))
+        .collect();
+    if !state.completed.is_empty() {
+        ctx.out.success(format!(
+            "Skipping {} completed step(s)",
+            state.completed.len()
+        ));
+    }
+
+    // Steps read the options from a snapshot while their results are recorded in `state`
+    let plan = state.clone();
+    let included = plan.steps();
+    state.failed.clear();
+    let started = Instant::now();
+    let overall = if ctx.dry_run {
+        indicatif::ProgressBar::hidden()
+    } else {
+        ctx.out.overall(included.len())
+    };
+    overall.set_position(state.completed.len() as u64);
+    let mut timings = Vec::new();
+    let mut running = FuturesUnordered::new();
+    let mut error = None;
+
+    loop {
+        if error.is_none() {
+            let (ready, waiting): (Vec<Step>, Vec<Step>) = pending.into_iter().partition(|step| {
+                step.deps()
+                    .iter()
+                    .all(|dep| !included.contains(dep) || state.completed.contains(dep))
+            });
+            pending = waiting;
+            for step in ready {
+                let plan = &plan;
+                running.push(async move {
+                    let started = Instant::now();
+                    (step, step.run(ctx, plan).await, started.elapsed())
+                });
+            }
+        }
+
+        let Some((step, result, duration)) = running.next().await else {
+            break;
+        };
+        match result {
+            Ok(outcome) => {
+                match outcome {
+                    Outcome::Done => {}
+                    Outcome::GithubRepo(repo) => state.github_repo = repo,
+                    Outcome::RemoteUrl(url) => state.remote_url = Some(url),
+                }
+                let status = if ctx.was_skipped(step.id()) {
+                    StepStatus::Skipped
+                } else {
+                    StepStatus::Done
+                };
+                timings.push((step, status, duration));
+                overall.inc(1);
+                state.completed.push(step);
+                // Like a failed step, so the running ones still finish and are reported
+                if !ctx.dry_run
+                    && state.has_root()
+                    && let Err(err) = state.save(&ctx.root)
+                {
+                    error.get_or_insert(err);
+                }
+            }
+            Err(err) => {
+                timings.push((step, StepStatus::Failed, duration));
+                state.failed.push(step);
+                error.get_or_insert(err);
+            }
+        }
+    }
+    overall.finish_and_clear();
+    if !ctx.dry_run {
+        report_timings(ctx, state, &timings, started.elapsed());
+    }
+
+    let Some(err) = error else {
+        return Ok(());
+    };
+    // Nothing to record if not even the project directory could be created
+    if !ctx.dry_run
+        && !state.completed.is_empty()
+        && state.has_root()
+        && let Err(save_err) = state.save(&ctx.root)
+    {
+        ctx.out.warn(format!("{:#}", save_err));
+    }
+    Err(err)
+}
+
+/// Prints how long each step took and how it ended, so slow or failing parts stand out
+fn report_timings(
+    ctx: &Ctx,
+    state: &State,
+    timings: &[(Step, StepStatus, Duration)],
+    elapsed: Duration,
+) {
+    let rows: Vec<(Step, StepStatus, Option<Duration>)> = state
+        .steps()
+        .into_iter()
+        .map(
+            |step| match timings.iter().find(|(timed, ..)| *timed == step) {
+                Some(&(_, status, duration)) => (step, status, Some(duration)),
+                None if state.completed.contains(&step) => (step, StepStatus::Previous, None),
+                None => (step, StepStatus::NotRun, None),
+            },
+        )
+        .collect();
+
+    ctx.out.event(
+        "step_timings",
+        serde_json::json!({
+            "steps": rows
+                .iter()
+                .map(|(step, status, duration)| serde_json::json!({
+                    "step": step.id(),
+                    "status": status,
+                    "duration_ms": duration.map(|d| d.as_millis() as u64),
+                }))
+                .collect::<Vec<_>>(),
+            "elapsed_ms": elapsed.as_millis() as u64,
+        }),
+    );
+
+    ctx.out.header("⏱️", "Step Timings");
+    let width = rows
+        .iter()
+        .map(|(step, ..)| step.id().len())
+        .max()
+        .unwrap_or(0);
+    for (step, status, duration) in &rows {
+        let duration = duration
+            .map(|d| format!("{:.1}s", d.as_secs_f64()))
+            .unwrap_or_default();
+        // Pad before coloring, since escape codes would count towards the width
+        let label = format!("{:<12}", status.label()).color(status.color());
+        let row = format!(
+            "  {:<width$}  {}  {:>7}",
+            step.id(),
+            label,
+            duration,
+            width = width
+        );
+        ctx.out.print(row.trim_end());
+    }
+    ctx.out.print(format!(
+        "  {:<width$}  {:<12}  {:>7}",
+        "total",
+        "",
+        format!("{:.1}s", elapsed.as_secs_f64()),
+        width = width
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Whether `step` cannot start before `dep` has completed
+    fn waits_for(step: Step, dep: Step) -> bool {
+        step.deps()
+            .iter()
+            .any(|&direct| direct == dep || waits_for(direct, dep))
+    }
+
+    #[test]
+    fn steps_in_the_project_directory_wait_for_it() {
+        // These only run global commands, outside the project directory
+        let global = [
+            Step::CreateDir,
+            Step::GitUserName,
+            Step::GitUserEmail,
+            Step::CheckUv,
+            Step::InstallPython,
+        ];
+        for step in Step::ALL {
+            if !global.contains(&step) {
+                assert!(waits_for(step, Step::CreateDir), "{:?}", step);
+            }
+        }
+    }
+
+    #[test]
+    fn steps_editing_pyproject_do_not_overlap() {
+        // `uv init`, `uv python pin`, `uv add` and the license step all write pyproject.toml
+        let editors = [
+            Step::UvInit,
+            Step::PinPython,
+            Step::InstallRequirements,
+            Step::DownloadLicense,
+        ];
+        for (i, &a) in editors.iter().enumerate() {
+            for &b in &editors[i + 1..] {
+                assert!(waits_for(b, a), "{:?} may run with {:?}", b, a);
+            }
+        }
+    }
+}
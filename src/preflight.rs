@@ -0,0 +1,320 @@
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use std::env;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+use crate::cli::{GitIdentityArgs, GlobalArgs, InitArgs, NewArgs};
+use crate::config::ProjectSettings;
+use crate::git_config_value;
+use crate::gitignore::Section;
+use crate::http;
+use crate::license;
+use crate::log::RunLog;
+use crate::output::Output;
+use crate::pipeline::State;
+use crate::source;
+use crate::staging_dir_for;
+
+/// Validates everything `pycargo new` needs before any directory, file or git config is touched
+pub async fn check_new(
+    args: &NewArgs,
+    settings: &ProjectSettings,
+    global: &GlobalArgs,
+    log: &RunLog,
+    out: &Output,
+) -> Result<()> {
+    let mut problems = Vec::new();
+
+    check_project_name(&args.name, &mut problems).await;
+    check_unfinished_setup(&args.name, &mut problems);
+    check_tools(&mut problems);
+    if let Some(version) = &settings.python {
+        check_python_version(version, &mut problems);
+    }
+    if let Err(err) = settings.license() {
+        problems.push(err.to_string());
+    }
+    check_sources(settings, &mut problems);
+    if global.non_interactive {
+        check_git_identity(&args.project.identity, log, &mut problems).await;
+    }
+    if let Some(github) = &settings.github {
+        check_repo_name(&github.name, &mut problems);
+        check_github_token(github.private, global.dry_run, log, out, &mut problems).await;
+    }
+
+    report(out, problems)
+}
+
+/// Validates everything `pycargo init` needs before any file is touched
+pub async fn check_init(
+    args: &InitArgs,
+    settings: &ProjectSettings,
+    global: &GlobalArgs,
+    log: &RunLog,
+    out: &Output,
+) -> Result<()> {
+    let mut problems = Vec::new();
+
+    match fs::metadata(&args.path).await {
+        Ok(metadata) if !metadata.is_dir() => {
+            problems.push(format!("'{}' is not a directory", args.path.display()))
+        }
+        Ok(_) => {}
+        Err(_) => problems.push(format!(
+            "Directory '{}' does not exist",
+            args.path.display()
+        )),
+    }
+    check_tools(&mut problems);
+    if let Some(version) = &settings.python {
+        check_python_version(version, &mut problems);
+    }
+    if let Err(err) = settings.license() {
+        problems.push(err.to_string());
+    }
+    check_sources(settings, &mut problems);
+    if global.non_interactive {
+        check_git_identity(&args.project.identity, log, &mut problems).await;
+    }
+
+    report(out, problems)
+}
+
+/// Refuses to start over when a failed `new` run left progress that `pycargo resume` can continue
+fn check_unfinished_setup(name: &str, problems: &mut Vec<String>) {
+    let Ok(staging_dir) = staging_dir_for(Path::new(name)) else {
+        return;
+    };
+    if let Ok(Some(_)) = State::load(&staging_dir) {
+        problems.push(format!(
+            "An unfinished setup of '{0}' exists. Continue it with `pycargo resume {0}` or discard it with `pycargo resume {0} --abort`",
+            name
+        ));
+    }
+}
+
+fn report(out: &Output, problems: Vec<String>) -> Result<()> {
+    out.event("preflight", serde_json::json!({ "problems": problems }));
+    if problems.is_empty() {
+        out.success("Preflight checks passed");
+        return Ok(());
+    }
+
+    let list: Vec<String> = problems.iter().map(|p| format!("  • {}", p)).collect();
+    anyhow::bail!(
+        "{}",
+        out.failure(format!(
+            "Preflight checks failed, nothing was changed:\n{}",
+            list.join("\n")
+        ))
+    )
+}
+
+async fn check_project_name(name: &str, problems: &mut Vec<String>) {
+    let path = Path::new(name);
+    let Some(dir_name) = path.file_name().map(|n| n.to_string_lossy()) else {
+        problems.push(format!("'{}' is not a valid project name", name));
+        return;
+    };
+
+    if !is_valid_package_name(&dir_name) {
+        problems.push(format!(
+            "'{}' is not a valid Python project name: use letters, digits, '-', '_' or '.', starting and ending with a letter or digit",
+            dir_name
+        ));
+    }
+    if fs::metadata(path).await.is_ok() {
+        problems.push(format!("Directory '{}' already exists", name));
+    }
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty())
+        && fs::metadata(parent).await.is_err()
+    {
+        problems.push(format!(
+            "Parent directory '{}' does not exist",
+            parent.display()
+        ));
+    }
+}
+
+/// Mirrors the PEP 508 rule uv applies to the project name derived from the directory
+fn is_valid_package_name(name: &str) -> bool {
+    let bytes = name.as_bytes();
+    match (bytes.first(), bytes.last()) {
+        (Some(first), Some(last)) => {
+            first.is_ascii_alphanumeric()
+                && last.is_ascii_alphanumeric()
+                && bytes
+                    .iter()
+                    .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.'))
+        }
+        _ => false,
+    }
+}
+
+fn check_repo_name(name: &str, problems: &mut Vec<String>) {
+    let valid = !name.is_empty()
+        && name.len() <= 100
+        && name != "."
+        && name != ".."
+        && name
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.'));
+    if !valid {
+        problems.push(format!(
+            "'{}' is not a valid GitHub repository name: use up to 100 letters, digits, '-', '_' or '.'",
+            name
+        ));
+    }
+}
+
+fn check_python_version(version: &str, problems: &mut Vec<String>) {
+    if crate::requires_python(version).is_none() {
+        problems.push(format!(
+            "'{}' is not a valid Python version: use a version such as 3.12 or 3.12.4",
+            version
+        ));
+    }
+}
+
+/// Configured download sources must be usable and every pin must name a file pycargo fetches
+fn check_sources(settings: &ProjectSettings, problems: &mut Vec<String>) {
+    let sources = &settings.sources;
+    for (key, location) in [
+        ("gitignore-source", &sources.gitignore),
+        ("license-source", &sources.license),
+    ] {
+        if let Some(location) = location
+            && let Err(err) = source::validate(location)
+        {
+            problems.push(format!("{}: {}", key, err));
+        }
+    }
+    for (name, digest) in &sources.sha256 {
+        let known = Section::from_str(name, true).is_ok() || license::find(name).is_some();
+        if !known {
+            problems.push(format!(
+                "sha256: '{}' is neither a gitignore section nor a supported license",
+                name
+            ));
+        }
+        if let Err(err) = source::validate_sha256(digest) {
+            problems.push(format!("sha256.{}: {}", name, err));
+        }
+    }
+}
+
+fn check_tools(problems: &mut Vec<String>) {
+    if find_executable("git").is_none() {
+        problems.push("git was not found on PATH".to_string());
+    }
+    if find_executable("uv").is_none() && find_executable("pip").is_none() {
+        problems
+            .push("Neither uv nor pip was found on PATH, so uv cannot be installed".to_string());
+    }
+}
+
+/// Without prompts, the git identity must already be configured or passed as flags
+async fn check_git_identity(identity: &GitIdentityArgs, log: &RunLog, problems: &mut Vec<String>) {
+    for (key, flag) in [("user.name", "--git-name"), ("user.email", "--git-email")] {
+        if identity.value_for(key).is_some() {
+            continue;
+        }
+        if !matches!(git_config_value(log, key).await, Ok(Some(_))) {
+            problems.push(format!(
+                "Git {} is not configured and --non-interactive is set; pass {}",
+                key, flag
+            ));
+        }
+    }
+}
+
+async fn check_github_token(
+    private: bool,
+    dry_run: bool,
+    log: &RunLog,
+    out: &Output,
+    problems: &mut Vec<String>,
+) {
+    let Ok(token) = env::var("GITHUB_TOKEN") else {
+        problems.push("GITHUB_TOKEN environment variable is not set".to_string());
+        return;
+    };
+
+    if dry_run {
+        out.plan("GET https://api.github.com/user to verify GITHUB_TOKEN");
+        return;
+    }
+
+    // `public_repo` is enough for a public repository, a private one needs `repo`
+    let (needed, sufficient): (&str, &[&str]) = if private {
+        ("'repo' scope for a private repository", &["repo"])
+    } else {
+        ("'repo' or 'public_repo' scope", &["repo", "public_repo"])
+    };
+    match github_token_scopes(&token, log).await {
+        Ok(Some(scopes)) if !scopes.iter().any(|s| sufficient.contains(&s.as_str())) => problems
+            .push(format!(
+                "GITHUB_TOKEN lacks the {} (has: {})",
+                needed,
+                scopes.join(", ")
+            )),
+        Ok(_) => {}
+        Err(err) => problems.push(format!("{:#}", err)),
+    }
+}
+
+/// Verifies a GitHub token and returns its OAuth scopes.
+///
+/// Fine-grained tokens do not report scopes, in which case `None` is returned.
+pub async fn github_token_scopes(token: &str, log: &RunLog) -> Result<Option<Vec<String>>> {
+    let request = http::client()
+        .get("https://api.github.com/user")
+        .bearer_auth(token);
+    let response = http::send(request, log)
+        .await
+        .context("Could not reach GitHub to verify GITHUB_TOKEN")?;
+
+    if response.status() == reqwest::StatusCode::UNAUTHORIZED {
+        anyhow::bail!("GITHUB_TOKEN is invalid or expired");
+    }
+    if !response.status().is_success() {
+        anyhow::bail!(
+            "GitHub rejected GITHUB_TOKEN verification: HTTP {}",
+            response.status()
+        );
+    }
+
+    Ok(response
+        .headers()
+        .get("x-oauth-scopes")
+        .and_then(|v| v.to_str().ok())
+        .map(|v| {
+            v.split(',')
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect()
+        }))
+}
+
+/// Looks up an executable on PATH without running it
+pub fn find_executable(name: &str) -> Option<PathBuf> {
+    let path = env::var_os("PATH")?;
+    let extensions: Vec<String> = if cfg!(windows) {
+        env::var("PATHEXT")
+            .unwrap_or_else(|_| ".EXE;.CMD;.BAT".to_string())
+            .split(';')
+            .map(str::to_string)
+            .collect()
+    } else {
+        vec![String::new()]
+    };
+
+    env::split_paths(&path).find_map(|dir| {
+        extensions
+            .iter()
+            .map(|ext| dir.join(format!("{}{}", name, ext)))
+            .find(|candidate| candidate.is_file())
+    })
+}
@@ -0,0 +1,460 @@
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use std::env;
+use std::ffi::OsString;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+
+use crate::cli::ShellArgs;
+use crate::output::Output;
+use crate::pipeline::ensure_project_dir;
+
+/// Shells whose virtual environment activation differs
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Shell {
+    /// Windows Command Prompt
+    Cmd,
+    #[value(alias = "pwsh")]
+    Powershell,
+    Bash,
+    Zsh,
+    Fish,
+    #[value(alias = "nushell")]
+    Nu,
+}
+
+impl Shell {
+    /// Guesses the shell pycargo was started from.
+    ///
+    /// `$SHELL` is the login shell on Unix and is set by Git Bash on Windows;
+    /// nushell and PowerShell are recognised by the variables they export.
+    pub fn detect() -> Shell {
+        if env::var_os("NU_VERSION").is_some() {
+            return Shell::Nu;
+        }
+        // Always set on Windows, but only inside PowerShell elsewhere
+        if !cfg!(windows) && env::var_os("PSModulePath").is_some() {
+            return Shell::Powershell;
+        }
+        if let Some(shell) =
+            env::var_os("SHELL").and_then(|path| Shell::from_program(Path::new(&path)))
+        {
+            return shell;
+        }
+        if cfg!(windows) {
+            // cmd.exe defines PROMPT for its children, PowerShell does not
+            if env::var_os("PROMPT").is_some() {
+                Shell::Cmd
+            } else {
+                Shell::Powershell
+            }
+        } else {
+            Shell::Bash
+        }
+    }
+
+    /// Recognises a shell from its executable, e.g. `/usr/bin/fish` or `pwsh.exe`
+    pub fn from_program(program: &Path) -> Option<Shell> {
+        let name = program.file_stem()?.to_string_lossy().to_lowercase();
+        match name.as_str() {
+            "cmd" => Some(Shell::Cmd),
+            "powershell" | "pwsh" => Some(Shell::Powershell),
+            "bash" | "sh" => Some(Shell::Bash),
+            "zsh" => Some(Shell::Zsh),
+            "fish" => Some(Shell::Fish),
+            "nu" => Some(Shell::Nu),
+            _ => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Shell::Cmd => "Command Prompt",
+            Shell::Powershell => "PowerShell",
+            Shell::Bash => "bash",
+            Shell::Zsh => "zsh",
+            Shell::Fish => "fish",
+            Shell::Nu => "nushell",
+        }
+    }
+
+    /// Label used when bash and zsh share one line, as they activate the same way
+    fn group_label(self) -> &'static str {
+        match self {
+            Shell::Bash | Shell::Zsh => "bash/zsh",
+            _ => self.label(),
+        }
+    }
+
+    /// Command that activates `.venv` in the current directory, for Windows or Unix
+    pub fn activate_command(self, windows: bool) -> String {
+        let script = |file: &str| {
+            // Unix-style shells on Windows (Git Bash, fish) still expect forward slashes
+            let backslash = windows && matches!(self, Shell::Cmd | Shell::Powershell | Shell::Nu);
+            let dir = if windows { "Scripts" } else { "bin" };
+            if backslash {
+                format!(".venv\\{}\\{}", dir, file)
+            } else {
+                format!(".venv/{}/{}", dir, file)
+            }
+        };
+        match self {
+            Shell::Cmd => script("activate.bat"),
+            Shell::Powershell => script("Activate.ps1"),
+            Shell::Bash | Shell::Zsh => format!("source {}", script("activate")),
+            Shell::Fish => format!("source {}", script("activate.fish")),
+            Shell::Nu => format!("overlay use {}", script("activate.nu")),
+        }
+    }
+}
+
+/// Activation commands for every shell used on this platform, labelled by shell
+pub fn all_activations() -> Vec<(&'static str, String)> {
+    let windows = cfg!(windows);
+    let mut shells = Vec::new();
+    if windows {
+        shells.push(Shell::Cmd);
+    }
+    shells.extend([Shell::Powershell, Shell::Bash, Shell::Fish, Shell::Nu]);
+    shells
+        .into_iter()
+        .map(|shell| (shell.group_label(), shell.activate_command(windows)))
+        .collect()
+}
+
+/// "Getting Started" section for a generated README, covering the shells of every platform
+pub fn readme_section() -> String {
+    let rows = [
+        ("Windows", Shell::Cmd, true),
+        ("Windows", Shell::Powershell, true),
+        ("Windows (Git Bash)", Shell::Bash, true),
+        ("macOS / Linux", Shell::Bash, false),
+        ("macOS / Linux", Shell::Fish, false),
+        ("macOS / Linux", Shell::Nu, false),
+        ("macOS / Linux", Shell::Powershell, false),
+    ];
+    let mut section = String::from(
+        "## Getting Started\n\nActivate the virtual environment with the command for your shell:\n\n| Platform | Shell | Command |\n| --- | --- | --- |\n",
+    );
+    for (platform, shell, windows) in rows {
+        writeln!(
+            section,
+            "| {} | {} | `{}` |",
+            platform,
+            shell.group_label(),
+            shell.activate_command(windows)
+        )
+        .expect("writing to a String cannot fail");
+    }
+    section
+}
+
+/// Starts an interactive shell with the `.venv` of the enclosing project activated.
+///
+/// Exits with the shell's exit code once the user leaves it.
+pub async fn run(args: ShellArgs, out: &Output) -> Result<()> {
+    let cwd = env::current_dir().context("Failed to read the current directory")?;
+    let root = find_project(&cwd)?;
+    let venv = root.join(".venv");
+    if env::var_os("VIRTUAL_ENV").is_some_and(|active| Path::new(&active) == venv) {
+        anyhow::bail!(
+            "{}",
+            out.failure(format!(
+                "The virtual environment of {} is already active",
+                root.display()
+            ))
+        );
+    }
+
+    let program = args.shell.unwrap_or_else(default_program);
+    let name = root
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "venv".to_string());
+    let bin = venv.join(if cfg!(windows) { "Scripts" } else { "bin" });
+    let mut paths = vec![bin.clone()];
+    paths.extend(env::split_paths(&env::var_os("PATH").unwrap_or_default()));
+    let path = env::join_paths(paths).context("Failed to build PATH for the shell")?;
+
+    let mut command = Command::new(&program);
+    command
+        .env("VIRTUAL_ENV", &venv)
+        .env("VIRTUAL_ENV_PROMPT", &name)
+        .env("PATH", path)
+        .env_remove("PYTHONHOME");
+    activate_on_start(&mut command, &program, &root, &bin, &name)?;
+
+    out.success(format!(
+        "Starting {} with {} activated. Type `exit` to leave it.",
+        program.display(),
+        venv.display()
+    ));
+    let mut child = command
+        .spawn()
+        .with_context(|| format!("Failed to start {}", program.display()))?;
+    let status = loop {
+        tokio::select! {
+            status = child.wait() => break status.context("Failed to wait for the shell")?,
+            // Ctrl-C belongs to the shell; pycargo just keeps waiting for it
+            _ = tokio::signal::ctrl_c() => {}
+        }
+    };
+    std::process::exit(status.code().unwrap_or(1));
+}
+
+/// Finds the nearest directory at or above `start` holding a project with a `.venv`
+fn find_project(start: &Path) -> Result<PathBuf> {
+    start
+        .ancestors()
+        .find(|dir| {
+            dir.join(".venv").is_dir()
+                && (dir.join(".pycargo").is_dir() || dir.join("pyproject.toml").is_file())
+        })
+        .map(Path::to_path_buf)
+        .with_context(|| {
+            format!(
+                "No project with a .venv found in {} or its parent directories",
+                start.display()
+            )
+        })
+}
+
+/// The shell the user is running now, falling back to the platform default
+fn default_program() -> PathBuf {
+    match Shell::detect() {
+        Shell::Nu => PathBuf::from("nu"),
+        Shell::Powershell if cfg!(windows) => PathBuf::from("powershell.exe"),
+        Shell::Powershell => PathBuf::from("pwsh"),
+        Shell::Cmd => env::var_os("COMSPEC")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from("cmd.exe")),
+        Shell::Bash | Shell::Zsh | Shell::Fish => env::var_os("SHELL")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from("/bin/sh")),
+    }
+}
+
+/// Makes the shell run the venv's activation script on startup, which sets its prompt.
+///
+/// bash and zsh read their usual startup files first through a generated rc
+/// file in `.pycargo/`; other shells get a prompt variable only.
+fn activate_on_start(
+    command: &mut Command,
+    program: &Path,
+    root: &Path,
+    bin: &Path,
+    name: &str,
+) -> Result<()> {
+    let stem = program
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let script = |file: &str| bin.join(file).to_string_lossy().into_owned();
+    match stem.as_str() {
+        "bash" => {
+            let rc = write_rc_file(
+                root,
+                "bashrc",
+                format!(
+                    "[ -f ~/.bashrc ] && . ~/.bashrc\n{}\n",
+                    source_if_present(&script("activate"))
+                ),
+            )?;
+            command.arg("--rcfile").arg(rc).arg("-i");
+        }
+        "zsh" => {
+            // zsh has no --rcfile, so point ZDOTDIR at a directory whose startup files chain
+            // to the real ones. The user's .zshenv may move ZDOTDIR, which .zshrc then follows.
+            let home = env::var_os("ZDOTDIR")
+                .or_else(|| env::var_os("HOME"))
+                .unwrap_or_default();
+            write_rc_file(
+                root,
+                "zsh/.zshenv",
+                format!(
+                    "PYCARGO_ZDOTDIR=\"$ZDOTDIR\"\nZDOTDIR={}\n[ -f \"$ZDOTDIR/.zshenv\" ] && . \"$ZDOTDIR/.zshenv\"\nPYCARGO_USER_ZDOTDIR=\"$ZDOTDIR\"\nZDOTDIR=\"$PYCARGO_ZDOTDIR\"\n",
+                    sh_quote(&home.to_string_lossy())
+                ),
+            )?;
+            let rc = write_rc_file(
+                root,
+                "zsh/.zshrc",
+                format!(
+                    "ZDOTDIR=${{PYCARGO_USER_ZDOTDIR:-{}}}\nunset PYCARGO_ZDOTDIR PYCARGO_USER_ZDOTDIR\n[ -f \"$ZDOTDIR/.zshrc\" ] && . \"$ZDOTDIR/.zshrc\"\n{}\n",
+                    sh_quote(&home.to_string_lossy()),
+                    source_if_present(&script("activate"))
+                ),
+            )?;
+            command.env("ZDOTDIR", rc.parent().expect("rc file has a parent"));
+        }
+        "fish" => {
+            command.arg("--init-command").arg(format!(
+                "source '{}'",
+                script("activate.fish")
+                    .replace('\\', "\\\\")
+                    .replace('\'', "\\'")
+            ));
+        }
+        "nu" => {
+            command
+                .arg("--execute")
+                .arg(format!("overlay use '{}'", script("activate.nu")));
+        }
+        "pwsh" | "powershell" => {
+            command
+                .args(["-NoExit", "-ExecutionPolicy", "Bypass", "-Command"])
+                .arg(format!(
+                    "& '{}'",
+                    script("Activate.ps1").replace('\'', "''")
+                ));
+        }
+        "cmd" => {
+            command.arg("/k").arg(script("activate.bat"));
+        }
+        _ => {
+            let prompt: OsString = env::var_os("PS1").unwrap_or_else(|| "$ ".into());
+            let mut prefixed = OsString::from(format!("({}) ", name));
+            prefixed.push(prompt);
+            command.env("PS1", prefixed);
+        }
+    }
+    Ok(())
+}
+
+/// Writes a startup file below the project's `.pycargo/shell/` directory
+fn write_rc_file(root: &Path, relative: &str, contents: String) -> Result<PathBuf> {
+    let path = ensure_project_dir(root)?.join("shell").join(relative);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    std::fs::write(&path, contents)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+/// Sources a script from a POSIX shell rc file, skipping it if the venv lacks it
+fn source_if_present(script: &str) -> String {
+    let quoted = sh_quote(script);
+    format!("[ -f {0} ] && . {0}", quoted)
+}
+
+/// Quotes a value for a POSIX shell script
+fn sh_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn activate_command_on_windows() {
+        assert_eq!(
+            Shell::Cmd.activate_command(true),
+            ".venv\\Scripts\\activate.bat"
+        );
+        assert_eq!(
+            Shell::Powershell.activate_command(true),
+            ".venv\\Scripts\\Activate.ps1"
+        );
+        assert_eq!(
+            Shell::Bash.activate_command(true),
+            "source .venv/Scripts/activate"
+        );
+        assert_eq!(
+            Shell::Fish.activate_command(true),
+            "source .venv/Scripts/activate.fish"
+        );
+        assert_eq!(
+            Shell::Nu.activate_command(true),
+            "overlay use .venv\\Scripts\\activate.nu"
+        );
+    }
+
+    #[test]
+    fn activate_command_on_unix() {
+        assert_eq!(Shell::Cmd.activate_command(false), ".venv/bin/activate.bat");
+        assert_eq!(
+            Shell::Powershell.activate_command(false),
+            ".venv/bin/Activate.ps1"
+        );
+        assert_eq!(
+            Shell::Bash.activate_command(false),
+            "source .venv/bin/activate"
+        );
+        assert_eq!(
+            Shell::Zsh.activate_command(false),
+            "source .venv/bin/activate"
+        );
+        assert_eq!(
+            Shell::Fish.activate_command(false),
+            "source .venv/bin/activate.fish"
+        );
+        assert_eq!(
+            Shell::Nu.activate_command(false),
+            "overlay use .venv/bin/activate.nu"
+        );
+    }
+
+    #[test]
+    fn from_program_recognises_paths_and_extensions() {
+        let shell = |program: &str| Shell::from_program(Path::new(program));
+        assert!(shell("/usr/bin/fish") == Some(Shell::Fish));
+        assert!(shell("/bin/sh") == Some(Shell::Bash));
+        assert!(shell("zsh") == Some(Shell::Zsh));
+        assert!(shell("nu") == Some(Shell::Nu));
+        assert!(shell("pwsh.exe") == Some(Shell::Powershell));
+        assert!(shell("PowerShell.EXE") == Some(Shell::Powershell));
+        assert!(shell("cmd.exe") == Some(Shell::Cmd));
+        assert!(shell("/usr/bin/tcsh").is_none());
+        assert!(shell("").is_none());
+    }
+
+    #[test]
+    fn all_activations_cover_this_platform() {
+        let activations = all_activations();
+        let labels: Vec<_> = activations.iter().map(|(label, _)| *label).collect();
+        if cfg!(windows) {
+            assert_eq!(
+                labels,
+                [
+                    "Command Prompt",
+                    "PowerShell",
+                    "bash/zsh",
+                    "fish",
+                    "nushell"
+                ]
+            );
+        } else {
+            assert_eq!(labels, ["PowerShell", "bash/zsh", "fish", "nushell"]);
+        }
+        assert!(
+            activations
+                .iter()
+                .any(|(_, command)| *command == Shell::Bash.activate_command(cfg!(windows)))
+        );
+    }
+
+    #[test]
+    fn readme_section_lists_both_platforms() {
+        let section = readme_section();
+        assert!(section.starts_with("## Getting Started\n"));
+        assert!(
+            section.contains("| Windows | Command Prompt | `.venv\\Scripts\\activate.bat` |\n")
+        );
+        assert!(section.contains("| Windows | PowerShell | `.venv\\Scripts\\Activate.ps1` |\n"));
+        assert!(
+            section
+                .contains("| Windows (Git Bash) | bash/zsh | `source .venv/Scripts/activate` |\n")
+        );
+        assert!(section.contains("| macOS / Linux | bash/zsh | `source .venv/bin/activate` |\n"));
+        assert!(section.contains("| macOS / Linux | fish | `source .venv/bin/activate.fish` |\n"));
+        assert!(
+            section.contains("| macOS / Linux | nushell | `overlay use .venv/bin/activate.nu` |\n")
+        );
+        assert!(section.contains("| macOS / Linux | PowerShell | `.venv/bin/Activate.ps1` |\n"));
+        assert_eq!(section.lines().filter(|l| l.starts_with("| ")).count(), 9);
+    }
+}
@@ -0,0 +1,92 @@
+use anyhow::{Context, Result};
+use reqwest::Url;
+use std::path::{Path, PathBuf};
+
+/// The local file a location refers to: a `file://` URL or a plain path.
+///
+/// Returns `None` for `http://` and `https://` URLs.
+pub fn local_path(location: &str) -> Option<PathBuf> {
+    if is_remote(location) {
+        return None;
+    }
+    if location.starts_with("file:") {
+        return Url::parse(location).ok()?.to_file_path().ok();
+    }
+    Some(PathBuf::from(location))
+}
+
+/// Location of `file` below a source, which is a base URL or a directory
+pub fn join(base: &str, file: &str) -> String {
+    if is_remote(base) || base.starts_with("file:") {
+        format!("{}/{}", base.trim_end_matches('/'), file)
+    } else {
+        Path::new(base).join(file).display().to_string()
+    }
+}
+
+/// Checks that a configured source is an http(s) URL, a valid `file://` URL or a path
+pub fn validate(location: &str) -> Result<()> {
+    if is_remote(location) {
+        Url::parse(location).with_context(|| format!("Invalid URL '{}'", location))?;
+    } else if location.starts_with("file:") {
+        local_path(location)
+            .with_context(|| format!("'{}' is not a valid file:// URL", location))?;
+    } else if location.is_empty() {
+        anyhow::bail!("The source cannot be empty");
+    }
+    Ok(())
+}
+
+/// Checks that a pinned checksum looks like a SHA-256 hex digest
+pub fn validate_sha256(digest: &str) -> Result<()> {
+    if digest.len() != 64 || !digest.bytes().all(|b| b.is_ascii_hexdigit()) {
+        anyhow::bail!("'{}' is not a SHA-256 hex digest", digest);
+    }
+    Ok(())
+}
+
+fn is_remote(location: &str) -> bool {
+    location.starts_with("http://") || location.starts_with("https://")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn local_path_of_urls_and_paths() {
+        assert_eq!(local_path("https://example.com/gitignore"), None);
+        assert_eq!(local_path("http://example.com"), None);
+        assert_eq!(
+            local_path("file:///srv/mirror"),
+            Some(PathBuf::from("/srv/mirror"))
+        );
+        assert_eq!(
+            local_path("mirror/licenses"),
+            Some(PathBuf::from("mirror/licenses"))
+        );
+    }
+
+    #[test]
+    fn join_urls_and_directories() {
+        assert_eq!(
+            join("https://example.com/gitignore/", "Global/macOS.gitignore"),
+            "https://example.com/gitignore/Global/macOS.gitignore"
+        );
+        assert_eq!(
+            join("file:///srv/licenses", "MIT.txt"),
+            "file:///srv/licenses/MIT.txt"
+        );
+        assert_eq!(
+            join("mirror", "MIT.txt"),
+            Path::new("mirror").join("MIT.txt").display().to_string()
+        );
+    }
+
+    #[test]
+    fn validate_sha256_digests() {
+        assert!(validate_sha256(&"a".repeat(64)).is_ok());
+        assert!(validate_sha256(&"a".repeat(63)).is_err());
+        assert!(validate_sha256(&"g".repeat(64)).is_err());
+    }
+}
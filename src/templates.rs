@@ -0,0 +1,80 @@
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+// Embed the template files into the binary using `include_str!`
+const BASIC_TEMPLATE: &str = include_str!("../templates/basic.txt");
+const ADVANCED_TEMPLATE: &str = include_str!("../templates/advanced.txt");
+const DATASCIENCE_TEMPLATE: &str = include_str!("../templates/datascience.txt");
+
+/// The requirement set a project is bootstrapped with
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SetupType {
+    Basic,
+    Advanced,
+    DataScience,
+    Blank,
+}
+
+impl SetupType {
+    /// Contents of the `requirements.txt` written for this setup
+    pub fn requirements(self) -> &'static str {
+        match self {
+            SetupType::Basic => BASIC_TEMPLATE,
+            SetupType::Advanced => ADVANCED_TEMPLATE,
+            SetupType::DataScience => DATASCIENCE_TEMPLATE,
+            SetupType::Blank => "",
+        }
+    }
+
+    /// Patterns added to `.gitignore` for the files this setup tends to produce
+    pub fn gitignore_entries(self) -> &'static [&'static str] {
+        match self {
+            SetupType::DataScience => &["data/raw/", "models/", "mlruns/", ".env"],
+            SetupType::Advanced => &[".env"],
+            SetupType::Basic | SetupType::Blank => &[],
+        }
+    }
+
+    /// Finds a `<setup>.txt` override in the configured template directories
+    pub fn find_override(self, template_dirs: &[PathBuf]) -> Option<PathBuf> {
+        template_dirs
+            .iter()
+            .map(|dir| dir.join(format!("{}.txt", self)))
+            .find(|path| path.is_file())
+    }
+
+    /// Loads this setup's requirements, preferring an override from the template directories
+    pub fn load_requirements(self, template_dirs: &[PathBuf]) -> Result<String> {
+        match self.find_override(template_dirs) {
+            Some(path) => read_template(&path),
+            None => Ok(self.requirements().to_string()),
+        }
+    }
+}
+
+fn read_template(path: &Path) -> Result<String> {
+    std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read template {}", path.display()))
+}
+
+/// Package names listed in a requirements file
+pub fn packages(requirements: &str) -> Vec<&str> {
+    requirements
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .collect()
+}
+
+impl fmt::Display for SetupType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = self
+            .to_possible_value()
+            .expect("setup types are never skipped");
+        f.write_str(value.get_name())
+    }
+}